@@ -1,90 +1,694 @@
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fmt::Write;
-use std::io::Write as IoWrite;
+use std::fmt;
+use std::io::{Cursor, Write as IoWrite};
+use std::path::{Path as FsPath, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{
     Router,
-    extract::Form,
-    response::Html,
+    extract::{DefaultBodyLimit, Form, Multipart, Path, Query, State},
+    http::{StatusCode, header},
+    response::{Html, IntoResponse, Redirect, Response},
     routing::{get, post},
 };
 use ollama_rs::{
     Ollama,
     generation::{completion::request::GenerationRequest, parameters::FormatType},
 };
-use rusqlite::{Connection, Transaction, params};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
 use serde::Deserialize;
 use tokio::net::TcpListener;
 use tower_http::services::ServeDir;
 
-fn init_db() -> rusqlite::Result<()> {
-    let conn = Connection::open("inventory.db")?;
+const DEFAULT_CONFIG_PATH: &str = "treasure_trove.toml";
 
-    conn.execute_batch(
-        r#"
-        PRAGMA foreign_keys = ON;
+/// Runtime settings, read from a TOML file and then overridden by `TROVE_*`
+/// environment variables. Every field is optional in the file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct Config {
+    db_path: String,
+    bind_addr: String,
+    printer_name: String,
+    ollama_url: String,
+    ollama_model: String,
+    /// Default for the submit form's "already in this container" choice.
+    merge_duplicates: bool,
+    /// Where uploaded photos and their thumbnails are written.
+    media_dir: String,
+    /// Names for auto-numbered containers, like `BIN-{:04}`; empty to always
+    /// type a name.
+    container_name_scheme: String,
+    /// Address phones reach this server at, like `http://trove.lan:3000`.
+    /// Container labels get a QR code for their page when it's set.
+    label_base_url: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            db_path: "inventory.db".to_string(),
+            bind_addr: "0.0.0.0:3000".to_string(),
+            printer_name: "zebra".to_string(),
+            ollama_url: "http://localhost:11434".to_string(),
+            ollama_model: "gemma3:1b".to_string(),
+            merge_duplicates: false,
+            media_dir: "media".to_string(),
+            container_name_scheme: String::new(),
+            label_base_url: String::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the file named by `TROVE_CONFIG` (default `treasure_trove.toml`).
+    /// A missing default file is fine; a missing explicit one is an error.
+    fn load() -> Result<Config, Box<dyn Error>> {
+        let explicit = std::env::var("TROVE_CONFIG").ok();
+        let path = explicit.as_deref().unwrap_or(DEFAULT_CONFIG_PATH);
+
+        let mut config = match std::fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| format!("{path}: {e}"))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && explicit.is_none() => {
+                Config::default()
+            }
+            Err(e) => return Err(format!("{path}: {e}").into()),
+        };
+
+        config.apply_env();
+        if let Some(scheme) = config.name_scheme()
+            && format_scheme(scheme, 1).is_none()
+        {
+            return Err(format!(
+                "container_name_scheme {scheme:?} needs one {{}} or {{:0N}} placeholder"
+            )
+            .into());
+        }
+        Ok(config)
+    }
+
+    fn name_scheme(&self) -> Option<&str> {
+        Some(self.container_name_scheme.as_str()).filter(|s| !s.is_empty())
+    }
+
+    /// Where a label's QR code sends the phone that scans it.
+    fn container_url(&self, id: i64) -> Option<String> {
+        let base = self.label_base_url.trim_end_matches('/');
+        (!base.is_empty()).then(|| format!("{base}/containers/{id}"))
+    }
 
-        CREATE TABLE IF NOT EXISTS containers (
-            id          INTEGER PRIMARY KEY AUTOINCREMENT,
-            name        TEXT NOT NULL UNIQUE,   -- globally unique
-            kind        TEXT,                   -- 'bin', 'drawer', etc. 
-            created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+    fn apply_env(&mut self) {
+        env_override(&mut self.db_path, "TROVE_DB_PATH");
+        env_override(&mut self.bind_addr, "TROVE_BIND_ADDR");
+        env_override(&mut self.printer_name, "TROVE_PRINTER_NAME");
+        env_override(&mut self.ollama_url, "TROVE_OLLAMA_URL");
+        env_override(&mut self.ollama_model, "TROVE_OLLAMA_MODEL");
+        env_override(&mut self.merge_duplicates, "TROVE_MERGE_DUPLICATES");
+        env_override(&mut self.media_dir, "TROVE_MEDIA_DIR");
+        env_override_clearable(
+            &mut self.container_name_scheme,
+            "TROVE_CONTAINER_NAME_SCHEME",
         );
-        CREATE TABLE IF NOT EXISTS items (
-            id            INTEGER PRIMARY KEY AUTOINCREMENT,
-            name          TEXT NOT NULL,
-            quantity      INTEGER NOT NULL,
-            container_id  INTEGER REFERENCES containers(id), -- nullable for loose items
-            location_hint TEXT,                              
-            created_at    TEXT NOT NULL DEFAULT (datetime('now'))
+        env_override_clearable(&mut self.label_base_url, "TROVE_LABEL_BASE_URL");
+    }
+}
+
+/// For settings where empty means off: unlike `env_override`, a variable
+/// that is set but blank clears the file's value.
+fn env_override_clearable(field: &mut String, var: &str) {
+    if let Ok(value) = std::env::var(var) {
+        *field = value.trim().to_string();
+    }
+}
+
+fn env_override<T: std::str::FromStr>(field: &mut T, var: &str) {
+    if let Some(value) = normalize_optional(std::env::var(var).ok()) {
+        match value.parse() {
+            Ok(parsed) => *field = parsed,
+            Err(_) => eprintln!("Ignoring {var}: can't parse {value:?}"),
+        }
+    }
+}
+
+type DbPool = r2d2::Pool<SqliteConnectionManager>;
+
+/// How long a connection waits on a locked database before giving up.
+const DB_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+struct AppState {
+    config: Arc<Config>,
+    ollama: Ollama,
+    db: DbPool,
+}
+
+impl AppState {
+    /// Runs `f` with a pooled connection on the blocking thread pool so slow
+    /// SQLite work never stalls the async runtime.
+    async fn with_db<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, AppError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            f(&mut conn)
+        })
+        .await?
+    }
+
+    /// Sends ZPL to the label printer on the blocking thread pool, since `lp`
+    /// can take a while, or hang, when the printer is offline.
+    async fn print_zpl(&self, zpl: String) -> Result<(), String> {
+        let printer_name = self.config.printer_name.clone();
+        tokio::task::spawn_blocking(move || {
+            send_zpl(&printer_name, &zpl).map_err(|e| e.to_string())
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()))
+    }
+}
+
+#[derive(Debug)]
+enum AppError {
+    /// SQLite rejected a statement.
+    Db(rusqlite::Error),
+    /// No pooled connection could be checked out.
+    Pool(r2d2::Error),
+    /// A blocking DB task panicked or was cancelled.
+    Task(tokio::task::JoinError),
+    /// Reading or writing a media file failed.
+    Io(std::io::Error),
+    /// The requested row does not exist.
+    NotFound(String),
+    /// The submitted form was rejected before anything was written.
+    BadRequest(String),
+    /// The label printer didn't take the job.
+    Print(String),
+    /// A submission was rolled back; `unsaved` lists what the user sent.
+    NotSaved {
+        cause: Box<AppError>,
+        unsaved: Vec<String>,
+    },
+}
+
+impl AppError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AppError::Db(rusqlite::Error::SqliteFailure(e, _))
+                if matches!(
+                    e.code,
+                    rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked
+                ) =>
+            {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            AppError::Db(_) | AppError::Pool(_) | AppError::Task(_) | AppError::Io(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Print(_) => StatusCode::BAD_GATEWAY,
+            AppError::NotSaved { cause, .. } => cause.status(),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Db(e) => write!(f, "database error: {e}"),
+            AppError::Pool(e) => write!(f, "could not get a database connection: {e}"),
+            AppError::Task(e) => write!(f, "database task failed: {e}"),
+            AppError::Io(e) => write!(f, "file error: {e}"),
+            AppError::NotFound(what) => write!(f, "{what} not found"),
+            AppError::BadRequest(msg) | AppError::Print(msg) => f.write_str(msg),
+            AppError::NotSaved { cause, .. } => write!(f, "{cause}"),
+        }
+    }
+}
+
+impl Error for AppError {}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::Db(e)
+    }
+}
+
+impl From<r2d2::Error> for AppError {
+    fn from(e: r2d2::Error) -> Self {
+        AppError::Pool(e)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io(e)
+    }
+}
+
+impl From<tokio::task::JoinError> for AppError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        AppError::Task(e)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if status.is_server_error() {
+            eprintln!("Request failed ({status}): {self}");
+        }
+
+        let mut body = format!(
+            "<h1>Something went wrong</h1><p>{}</p>",
+            html_escape(&self.to_string())
         );
-        "#,
-    )?;
+
+        if let AppError::NotSaved { unsaved, .. } = &self {
+            body.push_str("<p><strong>Nothing was saved.</strong>");
+            if !unsaved.is_empty() {
+                body.push_str(" These items were not recorded:</p><ul>");
+                for name in unsaved {
+                    body.push_str(&format!("<li>{}</li>", html_escape(name)));
+                }
+                body.push_str("</ul>");
+            } else {
+                body.push_str("</p>");
+            }
+        }
+
+        body.push_str(r#"<p><a href="/">Back to form</a> · <a href="/items">View Trove</a></p>"#);
+
+        (
+            status,
+            Html(render_page(
+                status.canonical_reason().unwrap_or("Error"),
+                &body,
+            )),
+        )
+            .into_response()
+    }
+}
+
+fn open_pool(db_path: &str) -> Result<DbPool, r2d2::Error> {
+    let manager = SqliteConnectionManager::file(db_path).with_init(|conn| {
+        conn.busy_timeout(DB_BUSY_TIMEOUT)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", "ON")
+    });
+
+    r2d2::Pool::new(manager)
+}
+
+/// Schema migrations, applied in order. A database's `PRAGMA user_version`
+/// is the number of entries it has already applied, so only ever append here.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema (IF NOT EXISTS so pre-migration databases adopt it)
+    r#"
+    CREATE TABLE IF NOT EXISTS containers (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        name        TEXT NOT NULL UNIQUE,   -- globally unique
+        kind        TEXT,                   -- 'bin', 'drawer', etc.
+        created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE TABLE IF NOT EXISTS items (
+        id            INTEGER PRIMARY KEY AUTOINCREMENT,
+        name          TEXT NOT NULL,
+        quantity      INTEGER NOT NULL,
+        container_id  INTEGER REFERENCES containers(id), -- nullable for loose items
+        location_hint TEXT,
+        created_at    TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    "#,
+    // 2: soft delete for items (the trash bin)
+    r#"
+    ALTER TABLE items ADD COLUMN deleted_at TEXT;
+    "#,
+    // 3: audit log
+    r#"
+    CREATE TABLE events (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        entity_type TEXT NOT NULL,           -- 'item' or 'container'
+        entity_id   INTEGER NOT NULL,        -- not a foreign key: outlives purges
+        action      TEXT NOT NULL,           -- 'create', 'update', 'move', ...
+        before_json TEXT,                    -- NULL for creates
+        after_json  TEXT,                    -- NULL for purges and deletes
+        source      TEXT NOT NULL,           -- 'web_form', 'llm_parse', 'api'
+        created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX events_entity ON events (entity_type, entity_id);
+    "#,
+    // 4: stock movement ledger, opened with each item's current quantity
+    r#"
+    CREATE TABLE stock_movements (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        item_id     INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+        delta       INTEGER NOT NULL,        -- negative for check-outs
+        reason      TEXT NOT NULL,
+        who         TEXT,
+        created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX stock_movements_item ON stock_movements (item_id);
+    INSERT INTO stock_movements (item_id, delta, reason, created_at)
+        SELECT id, quantity, 'opening balance', created_at FROM items;
+    "#,
+    // 5: photos attached to exactly one item or container
+    r#"
+    CREATE TABLE photos (
+        id           INTEGER PRIMARY KEY AUTOINCREMENT,
+        item_id      INTEGER REFERENCES items(id) ON DELETE CASCADE,
+        container_id INTEGER REFERENCES containers(id) ON DELETE CASCADE,
+        file_name    TEXT NOT NULL,          -- relative to media_dir
+        thumb_name   TEXT NOT NULL,
+        created_at   TEXT NOT NULL DEFAULT (datetime('now')),
+        CHECK ((item_id IS NULL) != (container_id IS NULL))
+    );
+    CREATE INDEX photos_item ON photos (item_id);
+    CREATE INDEX photos_container ON photos (container_id);
+    "#,
+    // 6: tags
+    r#"
+    CREATE TABLE tags (
+        id    INTEGER PRIMARY KEY AUTOINCREMENT,
+        name  TEXT NOT NULL UNIQUE            -- normalized: lowercase, single spaces
+    );
+    CREATE TABLE item_tags (
+        item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+        tag_id  INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+        PRIMARY KEY (item_id, tag_id)
+    );
+    CREATE INDEX item_tags_tag ON item_tags (tag_id);
+    "#,
+    // 7: free-form key/value attributes (brand, size, thread pitch, ...)
+    r#"
+    CREATE TABLE item_attributes (
+        item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+        key     TEXT NOT NULL COLLATE NOCASE,
+        value   TEXT NOT NULL,
+        PRIMARY KEY (item_id, key)
+    );
+    "#,
+    // 8: units of measure. items.quantity and stock_movements.delta keep
+    // their INTEGER affinity; SQLite stores fractional amounts in them as REAL.
+    r#"
+    ALTER TABLE items ADD COLUMN unit TEXT NOT NULL DEFAULT 'each';
+    "#,
+    // 9: low-stock threshold, in the item's unit; NULL means don't track
+    r#"
+    ALTER TABLE items ADD COLUMN min_quantity REAL;
+    "#,
+    // 10: use-by date for consumables, as YYYY-MM-DD
+    r#"
+    ALTER TABLE items ADD COLUMN expires_on TEXT;
+    CREATE INDEX items_expires_on ON items (expires_on) WHERE expires_on IS NOT NULL;
+    "#,
+    // 11: purchase details for insurance; price is per unit, receipt is a
+    // file name in the media directory
+    r#"
+    ALTER TABLE items ADD COLUMN purchase_price REAL;
+    ALTER TABLE items ADD COLUMN purchase_date TEXT;
+    ALTER TABLE items ADD COLUMN serial_number TEXT;
+    ALTER TABLE items ADD COLUMN receipt_file TEXT;
+    "#,
+    // 12: loans to neighbours; open while returned_at is NULL
+    r#"
+    CREATE TABLE loans (
+        id          INTEGER PRIMARY KEY,
+        item_id     INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+        borrower    TEXT NOT NULL,
+        quantity    REAL NOT NULL,
+        due_on      TEXT,
+        loaned_at   TEXT NOT NULL DEFAULT (datetime('now')),
+        returned_at TEXT
+    );
+    CREATE INDEX loans_open ON loans (item_id) WHERE returned_at IS NULL;
+    "#,
+    // 13: parent/child links between items; quantity is how many of the
+    // child a complete kit needs
+    r#"
+    CREATE TABLE item_links (
+        parent_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+        child_id  INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+        kind      TEXT NOT NULL CHECK (kind IN ('kit_part', 'accessory')),
+        quantity  REAL NOT NULL DEFAULT 1,
+        PRIMARY KEY (parent_id, child_id),
+        CHECK (parent_id <> child_id)
+    );
+    CREATE INDEX item_links_child ON item_links (child_id);
+    "#,
+    // 14: containers inside containers (drawer in cabinet in garage)
+    r#"
+    ALTER TABLE containers ADD COLUMN parent_id INTEGER REFERENCES containers(id) ON DELETE SET NULL;
+    CREATE INDEX containers_parent ON containers (parent_id);
+    "#,
+    // 15: named locations instead of free-text hints. Hints that differ only
+    // in case or spacing become one location, spelled as first entered with
+    // runs of whitespace collapsed the way `resolve_location` does. A container
+    // whose items all shared a hint takes it over, and those items inherit it.
+    r#"
+    CREATE TABLE locations (
+        id          INTEGER PRIMARY KEY,
+        name        TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    -- Tabs, line breaks and no-break spaces become spaces, then each run of
+    -- spaces shrinks to one by way of two marker characters.
+    UPDATE items SET location_hint = trim(replace(replace(replace(
+        replace(replace(replace(replace(replace(replace(location_hint,
+            char(9), ' '), char(10), ' '), char(11), ' '), char(12), ' '), char(13), ' '),
+            char(160), ' '),
+        ' ', char(1) || char(2)), char(2) || char(1), ''), char(1) || char(2), ' '));
+    INSERT OR IGNORE INTO locations (name)
+        SELECT location_hint FROM items
+        WHERE location_hint <> ''
+        ORDER BY id;
+
+    ALTER TABLE items ADD COLUMN location_id INTEGER REFERENCES locations(id) ON DELETE SET NULL;
+    ALTER TABLE containers ADD COLUMN location_id INTEGER REFERENCES locations(id) ON DELETE SET NULL;
+    UPDATE items SET location_id =
+        (SELECT id FROM locations WHERE name = items.location_hint);
+    UPDATE containers SET location_id = (
+        SELECT min(i.location_id) FROM items i
+        WHERE i.container_id = containers.id
+        HAVING count(*) = count(i.location_id) AND count(DISTINCT i.location_id) = 1);
+    UPDATE items SET location_id = NULL
+        WHERE location_id = (SELECT location_id FROM containers WHERE id = items.container_id);
+    ALTER TABLE items DROP COLUMN location_hint;
+
+    -- Where each container is: its own location, else the nearest enclosing
+    -- container's. Depth-limited so a parent cycle can't recurse forever.
+    CREATE VIEW container_locations (container_id, location_id) AS
+        WITH RECURSIVE up (container_id, parent_id, location_id, depth) AS (
+            SELECT id, parent_id, location_id, 0 FROM containers
+            UNION ALL
+            SELECT up.container_id, c.parent_id, c.location_id, up.depth + 1
+            FROM up JOIN containers c ON c.id = up.parent_id
+            WHERE up.location_id IS NULL AND up.depth < 64
+        )
+        SELECT container_id, location_id FROM up WHERE location_id IS NOT NULL;
+    -- Where each item is: its own location, else its container's.
+    CREATE VIEW item_locations (item_id, location_id, name) AS
+        SELECT i.id, l.id, l.name
+        FROM items i
+        LEFT JOIN container_locations cl ON cl.container_id = i.container_id
+        JOIN locations l ON l.id = coalesce(i.location_id, cl.location_id);
+    "#,
+    // 16: drawer cabinets. A grid container has one child container per
+    // drawer, addressed by `cell` ('A1' is the top-left drawer).
+    r#"
+    ALTER TABLE containers ADD COLUMN grid_rows INTEGER;
+    ALTER TABLE containers ADD COLUMN grid_cols INTEGER;
+    ALTER TABLE containers ADD COLUMN cell TEXT;
+    CREATE UNIQUE INDEX containers_cell ON containers (parent_id, cell) WHERE cell IS NOT NULL;
+    "#,
+    // 17: last number handed out per container naming scheme, so numbers
+    // aren't reused after a container is deleted
+    r#"
+    CREATE TABLE name_sequences (
+        scheme  TEXT PRIMARY KEY,
+        last    INTEGER NOT NULL
+    );
+    "#,
+];
+
+/// Brings the schema up to date, one transaction per migration. Refuses to
+/// touch a database written by a newer binary.
+fn run_migrations(conn: &mut Connection) -> Result<(), Box<dyn Error>> {
+    let current: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    let latest = MIGRATIONS.len() as i64;
+
+    if current > latest {
+        return Err(format!(
+            "database schema version {current} is newer than this binary supports ({latest})"
+        )
+        .into());
+    }
+
+    for (version, sql) in (1..).zip(MIGRATIONS).skip(current as usize) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)
+            .map_err(|e| format!("migration {version} failed: {e}"))?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+        println!("Applied database migration {version}");
+    }
 
     Ok(())
 }
 
 #[tokio::main]
 async fn main() {
-    init_db().expect("failed to initialize database");
+    let config = Config::load().expect("failed to load config");
+    let db = open_pool(&config.db_path).expect("failed to open database");
+    run_migrations(&mut db.get().expect("failed to get DB connection"))
+        .expect("failed to migrate database");
+
+    std::fs::create_dir_all(&config.media_dir).expect("failed to create media_dir");
+    let media = ServeDir::new(&config.media_dir);
+
+    let ollama = Ollama::try_new(config.ollama_url.as_str()).expect("invalid ollama_url");
+    let state = AppState {
+        config: Arc::new(config),
+        ollama,
+        db,
+    };
 
     let app = Router::new()
         .route("/", get(show_form))
         .route("/submit", post(handle_submit))
         .route("/items", get(show_items))
-        .nest_service("/static", ServeDir::new("static"));
+        .route(
+            "/items/{id}/edit",
+            get(edit_item_form).post(handle_edit_item),
+        )
+        .route("/items/{id}/delete", post(handle_delete_item))
+        .route("/items/{id}/checkout", post(handle_check_out))
+        .route("/items/{id}/checkin", post(handle_check_in))
+        .route("/items/{id}/attributes", post(handle_edit_attributes))
+        .route("/items/{id}/loan", post(handle_lend_item))
+        .route("/items/{id}/kit", get(show_kit))
+        .route("/items/{id}/links", post(handle_add_link))
+        .route("/items/{id}/links/{child}/delete", post(handle_remove_link))
+        .route("/loans", get(show_loans))
+        .route("/loans/{id}/return", post(handle_return_loan))
+        .route("/trash", get(show_trash))
+        .route("/trash/{id}/restore", post(handle_restore_item))
+        .route("/trash/{id}/purge", post(handle_purge_item))
+        .route("/locations", get(show_locations))
+        .route("/locations/{id}", post(handle_update_location))
+        .route("/containers", get(show_containers))
+        .route(
+            "/containers/{id}",
+            get(show_container).post(handle_update_container),
+        )
+        .route("/containers/{id}/delete", post(handle_delete_container))
+        .route("/containers/{id}/move", post(handle_move_items))
+        .route("/containers/{id}/grid", post(handle_set_grid))
+        .route("/labels/print", post(handle_print_labels))
+        .route(
+            "/labels/blank",
+            get(show_blank_labels).post(handle_print_blank_labels),
+        )
+        .route(
+            "/items/{id}/photos",
+            post(handle_upload_item_photos).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)),
+        )
+        .route(
+            "/containers/{id}/photos",
+            post(handle_upload_container_photos).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)),
+        )
+        .route(
+            "/items/{id}/receipt",
+            post(handle_upload_receipt).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)),
+        )
+        .route("/items/{id}/receipt/delete", post(handle_delete_receipt))
+        .route("/photos/{id}/delete", post(handle_delete_photo))
+        .route("/reports/valuation", get(show_valuation))
+        .route("/reports/valuation.csv", get(export_valuation_csv))
+        .route("/shopping-list", get(show_shopping_list))
+        .route("/shopping-list/print", post(handle_print_shopping_list))
+        .route("/expiring", get(show_expiring))
+        .route("/tags", get(show_tags))
+        .route("/tags/{tag}", get(show_tag))
+        .nest_service("/static", ServeDir::new("static"))
+        .nest_service("/media", media)
+        .with_state(state.clone());
 
-    let listener = TcpListener::bind("0.0.0.0:3000")
+    let listener = TcpListener::bind(&state.config.bind_addr)
         .await
         .expect("failed to bind to address");
 
-    println!("Server running on http://localhost:3000");
+    println!("Server running on http://{}", state.config.bind_addr);
     axum::serve(listener, app).await.expect("server error");
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Container {
     id: i64,
     name: String,
     kind: Option<String>,
+    /// The container this one sits inside, if any.
+    parent_id: Option<i64>,
+    /// Its own location; `None` to take the enclosing container's.
+    location_id: Option<i64>,
+    /// Rows and columns of drawers, for grid cabinets.
+    grid: Option<(i64, i64)>,
+    /// Drawer address within the parent grid, like "B3".
+    cell: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Location {
+    id: i64,
+    name: String,
 }
 
 #[derive(Debug)]
 struct Item {
     id: i64,
     name: String,
-    quantity: i32,
+    quantity: f64,
+    /// Canonical unit from `canonical_unit`; "each" for counted things.
+    unit: String,
+    /// Restock once `quantity` falls to this; `None` if not tracked.
+    min_quantity: Option<f64>,
+    /// YYYY-MM-DD, for things that go off.
+    expires_on: Option<String>,
     container_id: Option<i64>,
+    /// Name of where the item is, whether its own or its container's.
     location: Option<String>,
+    /// The item's own location; `None` to take its container's.
+    location_id: Option<i64>,
+    /// Out on open loans: off the shelf, so not in `quantity`, but still owned.
+    lent: f64,
+}
+
+impl Item {
+    /// Lent-out stock still counts; it's coming back.
+    fn is_low(&self) -> bool {
+        self.min_quantity
+            .is_some_and(|min| self.quantity + self.lent <= min + QUANTITY_EPSILON)
+    }
 }
 
+/// Open-loan total for the item aliased `i`, as a SQL expression.
+const LENT_SQL: &str = "(SELECT coalesce(sum(quantity), 0) FROM loans
+     WHERE item_id = i.id AND returned_at IS NULL)";
+
 #[derive(Deserialize)]
 struct InputForm {
     text: String,
     container_select: Option<String>,
     container_new: Option<String>,
     location: Option<String>,
+    merge: Option<String>,
 }
 #[derive(Debug, Deserialize)]
 struct ParsedInventory {
@@ -94,17 +698,34 @@ struct ParsedInventory {
 #[derive(Debug, Deserialize)]
 struct ParsedItem {
     name: String,
-    quantity: i32,
+    quantity: f64,
+    #[serde(default)]
+    unit: Option<String>,
+    /// From phrases like "keep at least 5".
+    #[serde(default)]
+    min_quantity: Option<f64>,
+    /// From phrases like "expires March 2027".
+    #[serde(default)]
+    expires_on: Option<String>,
 }
 
-async fn show_form() -> Html<String> {
-    let container_list = match load_containers() {
-        Ok(list) => list,
-        Err(e) => {
-            eprintln!("Failed to load containers: {e}");
-            Vec::new()
-        }
-    };
+impl ParsedItem {
+    fn unit(&self) -> String {
+        canonical_unit(self.unit.as_deref().unwrap_or(""))
+    }
+}
+
+async fn show_form(State(state): State<AppState>) -> Result<Html<String>, AppError> {
+    let scheme = state.config.name_scheme().map(str::to_string);
+    let (container_list, locations, next_name) = state
+        .with_db(move |conn| {
+            let next_name = match scheme {
+                Some(scheme) => Some(peek_container_name(conn, &scheme)?),
+                None => None,
+            };
+            Ok((load_containers(conn)?, load_locations(conn)?, next_name))
+        })
+        .await?;
 
     let mut html = String::new();
 
@@ -128,21 +749,11 @@ async fn show_form() -> Html<String> {
               <textarea id="text" name="text" rows="8" cols="40" style="width: 100%;"></textarea><br><br>
         "#);
 
-    html.push_str(
-        r#"<label for="container_select">Select Container (optional):</label><br>
-      <select id="container_select" name="container_select" style="width: 100%;">"#,
-    );
-
-    html.push_str(r#"<option value="">-- None --</option>"#);
-    for c in &container_list {
-        let escaped = html_escape(&c.name);
-        html.push_str(&format!(
-            r#"<option value="{id}">{label}</option>"#,
-            id = c.id,
-            label = escaped,
-        ));
-    }
-    html.push_str("</select><br><br>");
+    html.push_str(&render_container_select(
+        &container_list,
+        None,
+        next_name.as_deref(),
+    ));
 
     html.push_str(
         r#"<label for="container_new">New Bin (if Other or new):</label><br>
@@ -150,11 +761,27 @@ async fn show_form() -> Html<String> {
 "#,
     );
 
-    html.push_str(
-        r#"<label for="location">Location (optional):</label><br>
-      <input id="location" name="location" type="text" style="width: 100%;" /><br><br>
+    html.push_str(&format!(
+        r#"<label for="location">Location (optional, else the container's):</label><br>
+      <input id="location" name="location" type="text" list="locations" style="width: 100%;" /><br><br>
+      {}
 "#,
-    );
+        render_location_datalist(&locations)
+    ));
+
+    let (merge_sel, separate_sel) = if state.config.merge_duplicates {
+        (" selected", "")
+    } else {
+        ("", " selected")
+    };
+    html.push_str(&format!(
+        r#"<label for="merge">If an item is already in that container:</label><br>
+      <select id="merge" name="merge" style="width: 100%;">
+        <option value="merge"{merge_sel}>Add to its quantity</option>
+        <option value="separate"{separate_sel}>Keep as a separate entry</option>
+      </select><br><br>
+"#,
+    ));
 
     html.push_str(
         r#"      <button type="submit">Submit</button>
@@ -163,18 +790,31 @@ async fn show_form() -> Html<String> {
 </html>"#,
     );
 
-    Html(html)
+    Ok(Html(html))
 }
 
-async fn handle_submit(Form(input): Form<InputForm>) -> Html<String> {
-    let parsed_items = match llm_parse(&input.text).await {
-        Ok(p) => p,
+async fn handle_submit(
+    State(state): State<AppState>,
+    Form(input): Form<InputForm>,
+) -> Result<Html<String>, AppError> {
+    if input.text.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "The list was empty, so there was nothing to save.".to_string(),
+        ));
+    }
+
+    let (parsed_items, source) = match llm_parse(&state, &input.text).await {
+        Ok(p) => (p, EventSource::LlmParse),
         Err(e) => {
             eprintln!("LLM parse failed: {e}");
-            vec![ParsedItem {
+            let raw = vec![ParsedItem {
                 name: input.text.trim().to_string(),
-                quantity: 1,
-            }]
+                quantity: 1.0,
+                unit: None,
+                min_quantity: None,
+                expires_on: None,
+            }];
+            (raw, EventSource::WebForm)
         }
     };
     //    let parsed_items = vec![
@@ -231,58 +871,89 @@ async fn handle_submit(Form(input): Form<InputForm>) -> Html<String> {
     //            quantity: 1,
     //        },
     //    ];
-    //need to convert container_select from Option<String> to Option<i64>
-    let container_select_id: Option<i64> = input
-        .container_select
-        .as_deref() // &str
-        .and_then(|s| {
-            let t = s.trim();
-            if t.is_empty() {
-                None // treat "" as None
-            } else {
-                t.parse::<i64>().ok() // invalid numbers -> None
-            }
-        });
+    let container_select_id = parse_container_select(input.container_select.as_deref());
+    let auto_scheme = auto_name_scheme(&state.config, input.container_select.as_deref());
 
     let container_new = input.container_new;
     let location = normalize_optional(input.location);
+    let merge = match input.merge.as_deref() {
+        Some("merge") => true,
+        Some("separate") => false,
+        _ => state.config.merge_duplicates,
+    };
 
-    let mut items: Vec<Item> = Vec::new();
-    let mut container_name: Option<String> = None;
+    let unsaved: Vec<String> = parsed_items
+        .iter()
+        .map(|pi| format_amount(pi.quantity, &pi.unit(), &pi.name))
+        .collect();
+    check_parsed_quantities(&parsed_items).map_err(|cause| AppError::NotSaved {
+        cause: Box::new(cause),
+        unsaved: unsaved.clone(),
+    })?;
 
-    {
-        let mut conn = Connection::open("inventory.db").expect("failed to open DB");
-        let tx = conn.transaction().expect("failed to start transaction");
-
-        let (container_id, name_opt) =
-            match choose_container(&tx, container_select_id, container_new) {
-                Ok(pair) => pair,
-                Err(e) => {
-                    eprintln!("Failed to resolve container: {e}");
-                    (None, None)
-                }
-            };
-        container_name = name_opt;
+    let (items, outcomes, container_name) = state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
 
-        items = parsed_items
-            .into_iter()
-            .map(|pi| Item {
-                id: 0,
-                name: pi.name,
-                quantity: pi.quantity,
-                container_id,
-                location: location.clone(),
-            })
-            .collect();
+            let (container_id, _) = choose_container(
+                &tx,
+                container_select_id,
+                container_new,
+                auto_scheme.as_deref(),
+            )?;
+            // Like migration 15, an item in its container's own location
+            // inherits it rather than naming it again.
+            let placed = container_location(&tx, container_id)?;
+            let location_id = resolve_location(&tx, location.as_deref())?
+                .filter(|&id| placed.as_ref().map(|l| l.id) != Some(id));
+            let location = match location_id {
+                Some(_) => location,
+                None => placed.map(|l| l.name),
+            };
+            let name_opt = match container_id {
+                Some(cid) => load_container_paths(&tx)?.get(&cid).map(|p| path_label(p)),
+                None => None,
+            };
+            let items: Vec<Item> = parsed_items
+                .into_iter()
+                .map(|pi| Item {
+                    id: 0,
+                    unit: pi.unit(),
+                    name: pi.name,
+                    quantity: pi.quantity,
+                    min_quantity: pi.min_quantity.filter(|m| m.is_finite() && *m >= 0.0),
+                    expires_on: pi.expires_on.as_deref().and_then(parse_date),
+                    container_id,
+                    location: location.clone(),
+                    location_id,
+                    lent: 0.0,
+                })
+                .collect();
 
-        if let Err(e) = save_items_tx(&tx, &items) {
-            eprintln!("Failed to save items: {e}");
-        }
+            let outcomes = save_items_tx(&tx, &items, merge, source)?;
 
-        tx.commit().expect("failed to commit transaction");
-    }
+            tx.commit()?;
+            Ok((items, outcomes, name_opt))
+        })
+        .await
+        .map_err(|cause| AppError::NotSaved {
+            cause: Box::new(cause),
+            unsaved,
+        })?;
 
-    if let Err(e) = print_zebra_label(&items, container_name.as_deref()) {
+    let page_url = items
+        .first()
+        .and_then(|item| item.container_id)
+        .and_then(|id| state.config.container_url(id));
+    let print_error = state
+        .print_zpl(zebra_label(
+            &items,
+            container_name.as_deref(),
+            page_url.as_deref(),
+        ))
+        .await
+        .err();
+    if let Some(e) = &print_error {
         eprintln!("Failed to print zebra label: {e}")
     }
 
@@ -293,8 +964,8 @@ async fn handle_submit(Form(input): Form<InputForm>) -> Html<String> {
     html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\"></head><body style=\"font-family: sans-serif; padding: 1rem;\">");
     html.push_str("<h1>Parsed Items</h1><ul>");
 
-    for item in &items {
-        let mut line = format!("{} × {}", item.quantity, html_escape(&item.name));
+    for (item, outcome) in items.iter().zip(&outcomes) {
+        let mut line = format_amount(item.quantity, &item.unit, &html_escape(&item.name));
 
         if let Some(container_id) = item.container_id {
             line.push_str(&format!(" — Container ID: {}", container_id));
@@ -302,6 +973,15 @@ async fn handle_submit(Form(input): Form<InputForm>) -> Html<String> {
         if let Some(ref loc) = item.location {
             line.push_str(&format!(" — Location: {}", html_escape(loc)));
         }
+        match outcome {
+            SaveOutcome::Inserted(id) => {
+                line.push_str(&format!(r#" — <a href="/items/{id}/edit">new</a>"#))
+            }
+            SaveOutcome::Merged { id, quantity, unit } => line.push_str(&format!(
+                r#" — <a href="/items/{id}/edit">merged</a> (now {})"#,
+                html_escape(&format_quantity(*quantity, unit))
+            )),
+        }
 
         html.push_str("<li>");
         html.push_str(&line);
@@ -309,17 +989,44 @@ async fn handle_submit(Form(input): Form<InputForm>) -> Html<String> {
     }
 
     html.push_str("</ul>");
+    if let Some(e) = print_error {
+        html.push_str(&format!(
+            "<p><strong>The items above were saved, but the label did not print:</strong> {}</p>",
+            html_escape(&e)
+        ));
+    }
     html.push_str(r#"<p><a href="/">Back</a></p>"#);
     html.push_str("</body></html>");
 
-    Html(html)
+    Ok(Html(html))
+}
+
+/// Rejects a parse with any quantity that isn't a positive number, which the
+/// model produces now and then (0, -2, NaN). Nothing is saved, so the list
+/// can be corrected and resubmitted whole.
+fn check_parsed_quantities(items: &[ParsedItem]) -> Result<(), AppError> {
+    let bad: Vec<&str> = items
+        .iter()
+        .filter(|pi| !(pi.quantity.is_finite() && pi.quantity > 0.0))
+        .map(|pi| pi.name.as_str())
+        .collect();
+    if bad.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::BadRequest(format!(
+        "Couldn't read a quantity greater than 0 for {}.",
+        bad.join(", ")
+    )))
 }
 
-async fn llm_parse(raw: &str) -> Result<Vec<ParsedItem>, Box<dyn Error + Send + Sync>> {
+async fn llm_parse(
+    state: &AppState,
+    raw: &str,
+) -> Result<Vec<ParsedItem>, Box<dyn Error + Send + Sync>> {
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("LLM Parse called");
     println!("Raw input text:\n{}", raw);
-    let ollama = Ollama::default();
+    let model = &state.config.ollama_model;
 
     let prompt = format!(
         r#"
@@ -333,19 +1040,29 @@ async fn llm_parse(raw: &str) -> Result<Vec<ParsedItem>, Box<dyn Error + Send +
         Rules:
         - Split the text into separate items.
         - Each item must have:
-          - quantity: integer >= 1
+          - quantity: number > 0, decimals allowed (2.5, 0.5)
+          - unit: one of {units}
           - name: short name for the object (no extra commentary)
         - If the item includes a number or mentions a vague quantity, use that as quantity.
         - Otherwise, default quantity to 1.
+        - Use a measuring unit when the text gives one ("2.5 m of wire", "1 lb of screws",
+          "half a box of nails"); otherwise use "each" and count the objects.
+        - If the text asks to keep a minimum on hand ("keep at least 5", "never fewer than 2"),
+          add "min_quantity" with that number, in the item's unit. Otherwise leave it out.
+        - If the text gives an expiry or use-by date ("expires March 2027", "best before 5/1/26"),
+          add "expires_on" as "YYYY-MM-DD"; with only a month, use its last day.
+          Otherwise leave it out.
         
         Return ONLY JSON, no explanations, exactly in this shape:
         
         {{
           "items": [
-            {{ "name": "box of nails", "quantity": 3 }},
-            {{ "name": "hammer", "quantity": 1 }},
-            {{ "name": "screws", "quantity": 10 }},
-            {{ "name": "wrench", "quantity": 2 }}
+            {{ "name": "nails", "quantity": 3, "unit": "box", "min_quantity": 1 }},
+            {{ "name": "hammer", "quantity": 1, "unit": "each" }},
+            {{ "name": "screws", "quantity": 10, "unit": "each" }},
+            {{ "name": "wrench", "quantity": 2, "unit": "each" }},
+            {{ "name": "14 AWG wire", "quantity": 2.5, "unit": "m" }},
+            {{ "name": "caulk", "quantity": 1, "unit": "each", "expires_on": "2027-03-31" }}
           ]
         }}
         
@@ -353,15 +1070,16 @@ async fn llm_parse(raw: &str) -> Result<Vec<ParsedItem>, Box<dyn Error + Send +
         
         \"\"\"{raw}\"\"\" 
         "#,
+        units = COMMON_UNITS.join(", "),
     );
 
     println!("Sending prompt to Ollama:\n{}", prompt);
 
-    let request = GenerationRequest::new("gemma3:1b".to_string(), prompt).format(FormatType::Json);
+    let request = GenerationRequest::new(model.clone(), prompt).format(FormatType::Json);
 
-    println!("Calling Ollama (model: gemma3:1b)...");
+    println!("Calling Ollama (model: {model})...");
 
-    let res = ollama.generate(request).await?;
+    let res = state.ollama.generate(request).await?;
 
     println!("Ollama responded!");
     println!("Raw response:\n{}", res.response);
@@ -370,14 +1088,37 @@ async fn llm_parse(raw: &str) -> Result<Vec<ParsedItem>, Box<dyn Error + Send +
     Ok(parsed.items)
 }
 
-async fn show_items() -> Html<String> {
-    let items = match load_items_from_db() {
-        Ok(items) => items,
-        Err(e) => {
-            eprintln!("Failed to load items: {e}");
-            Vec::new()
-        }
-    };
+#[derive(Deserialize)]
+struct SearchParams {
+    q: Option<String>,
+}
+
+async fn show_items(
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> Result<Html<String>, AppError> {
+    let search = normalize_optional(params.q);
+    let query = search.clone();
+    let (mut items, today, paths) = state
+        .with_db(move |conn| {
+            Ok((
+                load_items_from_db(conn, query.as_deref())?,
+                today(conn)?,
+                load_container_paths(conn)?,
+            ))
+        })
+        .await?;
+    // Group nested containers under their parents; the sort is stable, so
+    // items keep their newest-first order within a container.
+    let no_path = Vec::new();
+    items.sort_by_cached_key(|row| {
+        let path = row
+            .item
+            .container_id
+            .and_then(|cid| paths.get(&cid))
+            .unwrap_or(&no_path);
+        (row.item.container_id.is_none(), path_sort_key(path))
+    });
 
     let mut html = String::new();
 
@@ -394,28 +1135,49 @@ async fn show_items() -> Html<String> {
         "#,
     );
 
-    if items.is_empty() {
+    html.push_str(&format!(
+        r#"<form method="get" action="/items" style="margin-bottom: 0.75rem;">
+      <input name="q" type="search" value="{}" placeholder="Search names, places, tags, details" style="width: 75%;">
+      <button type="submit">Search</button>
+    </form>
+"#,
+        html_escape(search.as_deref().unwrap_or(""))
+    ));
+
+    if items.is_empty() && search.is_some() {
+        html.push_str(r#"<p><em>Nothing matches.</em> <a href="/items">Show everything</a></p>"#);
+    } else if items.is_empty() {
         html.push_str("<p><em>No items yet.</em></p>\n");
     } else {
         let mut current_heading: Option<String> = None;
         let mut box_open = false;
 
         for row in items {
-            let heading = match &row.container_name {
-                Some(name) => format!("Container: {}", name),
-                None => "Loose items (no container)".to_string(),
+            let path = row.item.container_id.and_then(|cid| paths.get(&cid));
+            let heading = match (path, &row.container_name) {
+                (Some(path), _) => format!("Container: {}", path_label(path)),
+                (None, Some(name)) => format!("Container: {}", name),
+                (None, None) => "Loose items (no container)".to_string(),
             };
+            let depth = path.map_or(0, |p| p.len().saturating_sub(1));
 
             if current_heading.as_deref() != Some(heading.as_str()) {
                 if box_open {
                     html.push_str("      </tbody></table></div>\n");
                 }
 
-                html.push_str(
-                    r#"<div style="border: 1px solid black; padding: 0.5rem; margin-bottom: 0.75rem;">
+                html.push_str(&format!(
+                    r#"<div style="border: 1px solid black; padding: 0.5rem; margin-bottom: 0.75rem; margin-left: {indent}rem;">
                     <div style="font-weight: bold; font-size: 0.9rem; margin-bottom: 0.25rem;">"#,
-                );
-                html.push_str(&html_escape(&heading));
+                    indent = depth as f64 * 0.75,
+                ));
+                match row.item.container_id {
+                    Some(cid) => html.push_str(&format!(
+                        r#"<a href="/containers/{cid}" style="color: inherit;">{}</a>"#,
+                        html_escape(&heading)
+                    )),
+                    None => html.push_str(&html_escape(&heading)),
+                }
                 html.push_str(
                     r#"</div>
                         <table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;">
@@ -428,18 +1190,46 @@ async fn show_items() -> Html<String> {
             }
 
             let item = row.item;
-            let mut line = format!("{} × {}", item.quantity, html_escape(&item.name));
+            let mut line = format_amount(item.quantity, &item.unit, &html_escape(&item.name));
 
             if let Some(ref loc) = item.location {
                 line.push_str(&format!(" — {}", html_escape(loc)));
             }
+            if let Some(ref borrowers) = row.on_loan_to {
+                line.push_str(&format!(
+                    r#" <a href="/loans" style="color: darkorange; font-size: 0.8rem;">on loan to {}</a>"#,
+                    html_escape(borrowers)
+                ));
+            }
+            if item.is_low() {
+                line.push_str(r#" <a href="/shopping-list" style="color: darkred; font-size: 0.8rem;">low</a>"#);
+            }
+            if let Some(ref date) = item.expires_on {
+                line.push_str(&render_expiry(date, &today));
+            }
+            for tag in &row.tags {
+                line.push_str(&render_tag_chip(tag));
+            }
+            line.push_str(&render_attribute_summary(&row.attributes));
 
             html.push_str(
                 r#"      <tr>
                     <td style="padding: 2px 4px; border-top: 1px solid #eee;">"#,
             );
+            if let Some(ref thumb) = row.thumb {
+                html.push_str(&render_thumb(thumb, "2rem"));
+            }
             html.push_str(&line);
-            html.push_str("</td>\n      </tr>\n");
+            html.push_str(&format!(
+                r#"</td>
+                    <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right; white-space: nowrap;">
+                      <a href="/items/{id}/edit">edit</a>
+                      <form method="post" action="/items/{id}/delete" style="display: inline;"><button type="submit">delete</button></form>
+                    </td>
+      </tr>
+"#,
+                id = item.id
+            ));
         }
 
         if box_open {
@@ -448,145 +1238,5108 @@ async fn show_items() -> Html<String> {
     }
 
     html.push_str(
-        r#"    <p style="margin-top: 1rem;"><a href="/">Back to form</a></p>
+        r#"    <p style="margin-top: 1rem;"><a href="/">Back to form</a> · <a href="/containers">Containers</a> · <a href="/locations">Locations</a> · <a href="/tags">Tags</a> · <a href="/shopping-list">Shopping list</a> · <a href="/expiring">Expiring</a> · <a href="/loans">Loans</a> · <a href="/reports/valuation">Valuation</a> · <a href="/trash">Trash</a></p>
             </body>
         </html>"#,
     );
 
-    Html(html)
+    Ok(Html(html))
 }
 
-#[derive(Debug)]
-struct ItemWithContainer {
-    item: Item,
-    container_name: Option<String>,
+#[derive(Deserialize)]
+struct ItemForm {
+    name: String,
+    quantity: String,
+    unit: Option<String>,
+    /// Blank to stop tracking stock for this item.
+    min_quantity: Option<String>,
+    expires_on: Option<String>,
+    container_select: Option<String>,
+    container_new: Option<String>,
+    location: Option<String>,
+    /// Comma-separated tag names.
+    tags: Option<String>,
+    purchase_price: Option<String>,
+    purchase_date: Option<String>,
+    serial_number: Option<String>,
 }
 
-fn load_items_from_db() -> rusqlite::Result<Vec<ItemWithContainer>> {
-    let conn = Connection::open("inventory.db")?;
-
-    let mut stmt = conn.prepare(
-        r#"
-        SELECT 
-            i.id,
-            i.name,
-            i.quantity,
-            i.container_id,
-            i.location_hint,
-            c.name
-        FROM items i
-        LEFT JOIN containers c ON i.container_id = c.id
-        ORDER BY 
-            c.name IS NULL,    -- containers first, loose items last
-            c.name ASC,
-            datetime(i.created_at) DESC
-        "#,
-    )?;
-
-    let rows = stmt.query_map([], |row| {
-        Ok(ItemWithContainer {
-            item: Item {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                quantity: row.get(2)?,
-                container_id: row.get(3)?,
-                location: row.get(4)?,
-            },
-            container_name: row.get(5)?,
+async fn edit_item_form(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Html<String>, AppError> {
+    let (item, tags, page) = state
+        .with_db(move |conn| {
+            Ok((
+                load_item(conn, id)?,
+                load_item_tags(conn, id)?,
+                load_item_page(conn, id)?,
+            ))
         })
-    })?;
+        .await?;
+    let purchase = &page.purchase;
 
-    let mut items = Vec::new();
-    for row_result in rows {
-        items.push(row_result?);
-    }
+    let form = ItemForm {
+        name: item.name,
+        quantity: format_number(item.quantity),
+        unit: Some(item.unit),
+        min_quantity: item.min_quantity.map(format_number),
+        expires_on: item.expires_on,
+        container_select: item.container_id.map(|c| c.to_string()),
+        container_new: None,
+        location: page
+            .locations
+            .iter()
+            .find(|l| Some(l.id) == item.location_id)
+            .map(|l| l.name.clone()),
+        tags: Some(tags.join(", ")),
+        purchase_price: purchase.price.map(|p| format!("{p:.2}")),
+        purchase_date: purchase.date.clone(),
+        serial_number: purchase.serial_number.clone(),
+    };
 
-    Ok(items)
+    Ok(Html(render_item_form(id, &form, &page, &[])))
 }
 
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-}
+async fn handle_edit_item(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(form): Form<ItemForm>,
+) -> Result<Response, AppError> {
+    let mut errors = Vec::new();
 
-fn load_containers() -> rusqlite::Result<Vec<Container>> {
-    let conn = Connection::open("inventory.db")?;
-    let mut stmt = conn.prepare("SELECT id, name, kind FROM containers ORDER BY name")?;
+    let name = form.name.trim().to_string();
+    if name.is_empty() {
+        errors.push("Name can't be empty.");
+    }
 
-    let rows = stmt.query_map([], |row| {
-        Ok(Container {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            kind: row.get(2)?,
-        })
-    })?;
+    let quantity = match form.quantity.trim().parse::<f64>() {
+        Ok(q) if q.is_finite() && q >= 0.0 => q,
+        _ => {
+            errors.push("Quantity must be a number, 0 or more.");
+            0.0
+        }
+    };
+    let unit = canonical_unit(form.unit.as_deref().unwrap_or(""));
+    let min_quantity = match form.min_quantity.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(raw) => match raw.parse::<f64>() {
+            Ok(m) if m.is_finite() && m >= 0.0 => Some(m),
+            _ => {
+                errors.push("Keep-at-least must be a number, 0 or more, or blank.");
+                None
+            }
+        },
+    };
+    let purchase_price = match form.purchase_price.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(raw) => match raw.trim_start_matches('$').replace(',', "").parse::<f64>() {
+            Ok(p) if p.is_finite() && p >= 0.0 => Some(p),
+            _ => {
+                errors.push("Purchase price must be a number, 0 or more, or blank.");
+                None
+            }
+        },
+    };
+    let purchase_date = match form.purchase_date.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(raw) => {
+            let date = parse_date(raw);
+            if date.is_none() {
+                errors.push("Purchase date must look like 2024-06-30, or be blank.");
+            }
+            date
+        }
+    };
+    let serial_number = normalize_optional(form.serial_number.clone());
+    let expires_on = match form.expires_on.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(raw) => {
+            let date = parse_date(raw);
+            if date.is_none() {
+                errors.push("Expiry date must look like 2027-03-31, or be blank.");
+            }
+            date
+        }
+    };
 
-    let mut containers = Vec::new();
-    for r in rows {
-        containers.push(r?);
+    if !errors.is_empty() {
+        let page = state
+            .with_db(move |conn| {
+                load_item(conn, id)?;
+                load_item_page(conn, id)
+            })
+            .await?;
+        let html = render_item_form(id, &form, &page, &errors);
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Html(html)).into_response());
     }
-    Ok(containers)
-}
 
-fn save_items_tx(tx: &rusqlite::Transaction, items: &[Item]) -> rusqlite::Result<()> {
-    let mut stmt = tx.prepare(
-        "INSERT INTO items (name, quantity, container_id, location_hint)
-         VALUES (?1, ?2, ?3, ?4)",
-    )?;
+    let container_select_id = parse_container_select(form.container_select.as_deref());
+    let container_new = form.container_new;
+    let location = normalize_optional(form.location);
+    let tags = parse_tags(form.tags.as_deref().unwrap_or(""));
 
-    for item in items {
-        stmt.execute(params![
-            &item.name,
-            item.quantity,
-            item.container_id,
-            &item.location,
-        ])?;
-    }
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let old = load_item(&tx, id)?;
+            let (container_id, _) =
+                choose_container(&tx, container_select_id, container_new, None)?;
+            let location_id = resolve_location(&tx, location.as_deref())?;
 
-    Ok(())
+            let before = item_snapshot(&tx, id)?;
+            tx.execute(
+                "UPDATE items
+                 SET name = ?1, quantity = ?2, unit = ?3, min_quantity = ?4, expires_on = ?5,
+                     container_id = ?6, location_id = ?7
+                 WHERE id = ?8",
+                params![
+                    name,
+                    quantity,
+                    unit,
+                    min_quantity,
+                    expires_on,
+                    container_id,
+                    location_id,
+                    id
+                ],
+            )?;
+            // The ledger is kept in the item's current unit, so a unit change
+            // restates the balance in one step.
+            if unit != old.unit {
+                let reason = format!("unit changed from {} to {}", old.unit, unit);
+                record_movement(&tx, id, quantity - old.quantity, &reason, None)?;
+            } else if quantity != old.quantity {
+                record_movement(&tx, id, quantity - old.quantity, "count corrected", None)?;
+            }
+            tx.execute(
+                "UPDATE items SET purchase_price = ?1, purchase_date = ?2, serial_number = ?3
+                 WHERE id = ?4",
+                params![purchase_price, purchase_date, serial_number, id],
+            )?;
+            set_item_tags(&tx, id, &tags)?;
+            let after = item_snapshot(&tx, id)?;
+
+            let renamed = old.name != name;
+            let moved = old.container_id != container_id || old.location_id != location_id;
+            let recounted = old.quantity != quantity || old.unit != unit;
+            let action = if before == after {
+                None
+            } else {
+                Some(match (renamed, moved, recounted) {
+                    (false, true, false) => EventAction::Move,
+                    (false, false, true) => EventAction::QuantityChange,
+                    _ => EventAction::Update,
+                })
+            };
+            if let Some(action) = action {
+                record_event(
+                    &tx,
+                    EntityKind::Item,
+                    id,
+                    action,
+                    before,
+                    after,
+                    EventSource::WebForm,
+                )?;
+            }
+
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to("/items").into_response())
+}
+
+/// Everything on the item page besides the form fields themselves.
+struct ItemPage {
+    /// The stored quantity, checked against the ledger total.
+    quantity: f64,
+    unit: String,
+    containers: Vec<Container>,
+    locations: Vec<Location>,
+    movements: Vec<Movement>,
+    photos: Vec<Photo>,
+    attributes: Vec<(String, String)>,
+    /// Keys used anywhere, for the attribute editor's suggestions.
+    attribute_keys: Vec<String>,
+    purchase: Purchase,
+    /// Open loans only.
+    loans: Vec<Loan>,
+    parts: Vec<KitPart>,
+    /// Items this one is a part or accessory of: id, name, link kind.
+    part_of: Vec<(i64, String, LinkKind)>,
+    /// Every other live item, for the add-a-part dropdown.
+    item_choices: Vec<(i64, String)>,
+    events: Vec<Event>,
+}
+
+fn load_item_page(conn: &Connection, id: i64) -> Result<ItemPage, AppError> {
+    let item = load_item(conn, id)?;
+    Ok(ItemPage {
+        quantity: item.quantity,
+        unit: item.unit,
+        containers: load_containers(conn)?,
+        locations: load_locations(conn)?,
+        movements: load_movements(conn, id)?,
+        photos: load_photos(conn, EntityKind::Item, id)?,
+        attributes: load_item_attributes(conn, id)?,
+        attribute_keys: load_attribute_keys(conn)?,
+        purchase: load_purchase(conn, id)?,
+        loans: load_loans(conn, Some(id))?,
+        parts: load_kit_parts(conn, id)?,
+        part_of: load_part_of(conn, id)?,
+        item_choices: load_item_choices(conn, id)?,
+        events: load_events(conn, EntityKind::Item, id)?,
+    })
+}
+
+fn render_item_form(id: i64, form: &ItemForm, page: &ItemPage, errors: &[&str]) -> String {
+    let mut body = String::new();
+
+    body.push_str(r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Edit Item</h1>"#);
+
+    if !errors.is_empty() {
+        body.push_str(r#"<ul style="color: darkred;">"#);
+        for e in errors {
+            body.push_str(&format!("<li>{}</li>", html_escape(e)));
+        }
+        body.push_str("</ul>");
+    }
+
+    body.push_str(&format!(
+        r#"<form method="post" action="/items/{id}/edit">
+      <label for="name">Name:</label><br>
+      <input id="name" name="name" type="text" value="{name}" style="width: 100%;" /><br><br>
+      <label for="quantity">Quantity:</label><br>
+      <input id="quantity" name="quantity" type="number" min="0" step="any" value="{quantity}" style="width: 100%;" /><br><br>
+      <label for="unit">Unit:</label><br>
+      <input id="unit" name="unit" type="text" list="units" value="{unit}" style="width: 100%;" /><br><br>
+      {units}
+      <label for="min_quantity">Keep at least (optional, for the shopping list):</label><br>
+      <input id="min_quantity" name="min_quantity" type="number" min="0" step="any" value="{min_quantity}" style="width: 100%;" /><br><br>
+      <label for="expires_on">Expires on (optional):</label><br>
+      <input id="expires_on" name="expires_on" type="date" value="{expires_on}" style="width: 100%;" /><br><br>
+"#,
+        name = html_escape(&form.name),
+        quantity = html_escape(&form.quantity),
+        unit = html_escape(form.unit.as_deref().unwrap_or("each")),
+        min_quantity = html_escape(form.min_quantity.as_deref().unwrap_or("")),
+        expires_on = html_escape(form.expires_on.as_deref().unwrap_or("")),
+        units = render_unit_datalist(),
+    ));
+
+    let selected = parse_container_select(form.container_select.as_deref());
+    body.push_str(&render_container_select(&page.containers, selected, None));
+
+    body.push_str(&format!(
+        r#"<label for="container_new">New Bin (if Other or new):</label><br>
+      <input id="container_new" name="container_new" type="text" value="{container_new}" style="width: 100%;" /><br><br>
+      <label for="location">Location (optional, else the container's):</label><br>
+      <input id="location" name="location" type="text" list="locations" value="{location}" style="width: 100%;" /><br><br>
+      {locations}
+      <label for="tags">Tags (comma-separated, e.g. woodturning, consumable):</label><br>
+      <input id="tags" name="tags" type="text" value="{tags}" style="width: 100%;" /><br><br>
+      <label for="purchase_price">Purchase price per {per} (optional):</label><br>
+      <input id="purchase_price" name="purchase_price" type="text" inputmode="decimal" value="{purchase_price}" style="width: 100%;" /><br><br>
+      <label for="purchase_date">Purchase date (optional):</label><br>
+      <input id="purchase_date" name="purchase_date" type="date" value="{purchase_date}" style="width: 100%;" /><br><br>
+      <label for="serial_number">Serial number (optional):</label><br>
+      <input id="serial_number" name="serial_number" type="text" value="{serial_number}" style="width: 100%;" /><br><br>
+      <button type="submit">Save</button>
+    </form>"#,
+        container_new = html_escape(form.container_new.as_deref().unwrap_or("")),
+        location = html_escape(form.location.as_deref().unwrap_or("")),
+        locations = render_location_datalist(&page.locations),
+        tags = html_escape(form.tags.as_deref().unwrap_or("")),
+        per = if page.unit == "each" { "item".to_string() } else { html_escape(&page.unit) },
+        purchase_price = html_escape(form.purchase_price.as_deref().unwrap_or("")),
+        purchase_date = html_escape(form.purchase_date.as_deref().unwrap_or("")),
+        serial_number = html_escape(form.serial_number.as_deref().unwrap_or("")),
+    ));
+
+    body.push_str(&render_attribute_editor(id, page));
+    body.push_str(&render_receipt(id, &page.purchase));
+    body.push_str(&render_photos(&page.photos, &format!("/items/{id}/photos")));
+    body.push_str(&render_movements(id, page));
+    body.push_str(&render_item_loans(id, page));
+    body.push_str(&render_item_links(id, page));
+    body.push_str(&render_timeline(&page.events));
+    body.push_str(r#"<p style="margin-top: 1rem;"><a href="/items">Back to Trove</a></p>"#);
+
+    render_page("Edit Item", &body)
+}
+
+/// Separator between levels of a container path.
+const PATH_SEPARATOR: &str = " › ";
+
+/// Each container's chain of (id, name) from its outermost ancestor down to
+/// itself. Updates refuse parent loops; should one exist anyway, the chain
+/// stops where it would repeat.
+fn container_paths(containers: &[Container]) -> HashMap<i64, Vec<(i64, String)>> {
+    let by_id: HashMap<i64, &Container> = containers.iter().map(|c| (c.id, c)).collect();
+    containers
+        .iter()
+        .map(|c| {
+            let mut path = Vec::new();
+            let mut seen = HashSet::new();
+            let mut current = Some(c);
+            while let Some(node) = current {
+                if !seen.insert(node.id) {
+                    break;
+                }
+                // A drawer's name repeats its cabinet's, so show the address.
+                path.push((
+                    node.id,
+                    node.cell.clone().unwrap_or_else(|| node.name.clone()),
+                ));
+                current = node.parent_id.and_then(|p| by_id.get(&p).copied());
+            }
+            path.reverse();
+            (c.id, path)
+        })
+        .collect()
+}
+
+fn load_container_paths(conn: &Connection) -> rusqlite::Result<HashMap<i64, Vec<(i64, String)>>> {
+    Ok(container_paths(&load_containers(conn)?))
+}
+
+/// "Garage › Red cabinet › Drawer 3", unescaped.
+fn path_label(path: &[(i64, String)]) -> String {
+    path.iter()
+        .map(|(_, name)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(PATH_SEPARATOR)
+}
+
+/// Sort key that keeps each container directly ahead of its children.
+fn path_sort_key(path: &[(i64, String)]) -> Vec<String> {
+    path.iter().map(|(_, name)| name.to_lowercase()).collect()
+}
+
+/// Breadcrumb links to each container on the path.
+fn render_breadcrumbs(path: &[(i64, String)]) -> String {
+    path.iter()
+        .map(|(id, name)| format!(r#"<a href="/containers/{id}">{}</a>"#, html_escape(name)))
+        .collect::<Vec<_>>()
+        .join(&html_escape(PATH_SEPARATOR))
+}
+
+/// The container dropdown shared by the submit and edit forms.
+/// `next_name`, if given, adds a choice to create a container under the
+/// configured naming scheme; it's a preview, as another form may take it first.
+fn render_container_select(
+    containers: &[Container],
+    selected: Option<i64>,
+    next_name: Option<&str>,
+) -> String {
+    let mut html = String::new();
+
+    html.push_str(
+        r#"<label for="container_select">Select Container (optional):</label><br>
+      <select id="container_select" name="container_select" style="width: 100%;">"#,
+    );
+
+    html.push_str(r#"<option value="">-- None --</option>"#);
+    if let Some(next_name) = next_name {
+        html.push_str(&format!(
+            r#"<option value="{NEW_CONTAINER_OPTION}">New: {}</option>"#,
+            html_escape(next_name)
+        ));
+    }
+    let paths = container_paths(containers);
+    let mut options: Vec<(i64, &Vec<(i64, String)>)> =
+        paths.iter().map(|(id, p)| (*id, p)).collect();
+    options.sort_by_cached_key(|(_, path)| path_sort_key(path));
+    for (id, path) in options {
+        html.push_str(&format!(
+            r#"<option value="{id}"{sel}>{label}</option>"#,
+            sel = if selected == Some(id) {
+                " selected"
+            } else {
+                ""
+            },
+            label = html_escape(&path_label(path)),
+        ));
+    }
+    html.push_str("</select><br><br>");
+
+    html
+}
+
+/// Blank rows offered below the existing attributes for adding new ones.
+const BLANK_ATTRIBUTE_ROWS: usize = 3;
+
+/// Replaces the item's attributes with the submitted `attr_key`/`attr_value`
+/// pairs. Rows with a blank key or value are dropped.
+async fn handle_edit_attributes(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(fields): Form<Vec<(String, String)>>,
+) -> Result<Redirect, AppError> {
+    let mut attributes: Vec<(String, String)> = form_values(&fields, "attr_key")
+        .zip(form_values(&fields, "attr_value"))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .filter(|(k, v)| !k.is_empty() && !v.is_empty())
+        .collect();
+    // Last one wins when the same key is entered twice.
+    attributes.reverse();
+    let mut seen = Vec::new();
+    attributes.retain(|(k, _)| {
+        let key = k.to_lowercase();
+        let fresh = !seen.contains(&key);
+        seen.push(key);
+        fresh
+    });
+
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            load_item(&tx, id)?;
+            let before = item_snapshot(&tx, id)?;
+
+            tx.execute(
+                "DELETE FROM item_attributes WHERE item_id = ?1",
+                params![id],
+            )?;
+            for (key, value) in &attributes {
+                tx.execute(
+                    "INSERT INTO item_attributes (item_id, key, value) VALUES (?1, ?2, ?3)",
+                    params![id, key, value],
+                )?;
+            }
+
+            let after = item_snapshot(&tx, id)?;
+            if after != before {
+                record_event(
+                    &tx,
+                    EntityKind::Item,
+                    id,
+                    EventAction::Update,
+                    before,
+                    after,
+                    EventSource::WebForm,
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to(&format!("/items/{id}/edit")))
+}
+
+fn render_attribute_editor(id: i64, page: &ItemPage) -> String {
+    let mut html = format!(
+        r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Details</h2>
+    <form method="post" action="/items/{id}/attributes">
+      <table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#
+    );
+
+    let blanks = std::iter::repeat_n(("", ""), BLANK_ATTRIBUTE_ROWS);
+    let rows = page
+        .attributes
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .chain(blanks);
+    for (key, value) in rows {
+        html.push_str(&format!(
+            r#"<tr>
+          <td style="padding: 2px 4px 2px 0; width: 40%;"><input name="attr_key" type="text" list="attribute_keys" value="{key}" placeholder="brand, size…" aria-label="detail name" style="width: 100%;"></td>
+          <td style="padding: 2px 0;"><input name="attr_value" type="text" value="{value}" aria-label="detail value" style="width: 100%;"></td>
+        </tr>"#,
+            key = html_escape(key),
+            value = html_escape(value),
+        ));
+    }
+
+    html.push_str(r#"</tbody></table><datalist id="attribute_keys">"#);
+    for key in &page.attribute_keys {
+        html.push_str(&format!(r#"<option value="{}">"#, html_escape(key)));
+    }
+    html.push_str(
+        r#"</datalist>
+      <p style="font-size: 0.8rem; color: gray;">Clear a value to remove that detail.</p>
+      <button type="submit">Save details</button>
+    </form>"#,
+    );
+
+    html
+}
+
+/// Compact "key: value · key: value" line shown under an item in lists.
+fn render_attribute_summary(attributes: &[(String, String)]) -> String {
+    if attributes.is_empty() {
+        return String::new();
+    }
+    let parts: Vec<String> = attributes
+        .iter()
+        .map(|(k, v)| format!("{}: {}", html_escape(k), html_escape(v)))
+        .collect();
+    format!(
+        r#"<br><small style="color: gray;">{}</small>"#,
+        parts.join(" · ")
+    )
+}
+
+#[derive(Debug)]
+struct Loan {
+    id: i64,
+    item_id: i64,
+    item_name: String,
+    unit: String,
+    borrower: String,
+    quantity: f64,
+    due_on: Option<String>,
+    loaned_at: String,
+}
+
+#[derive(Deserialize)]
+struct LoanForm {
+    borrower: String,
+    quantity: String,
+    due_on: Option<String>,
+}
+
+/// Open loans, soonest due first, for one item or for everything.
+fn load_loans(conn: &Connection, item_id: Option<i64>) -> rusqlite::Result<Vec<Loan>> {
+    let mut stmt = conn.prepare(
+        "SELECT l.id, l.item_id, i.name, i.unit, l.borrower, l.quantity, l.due_on, l.loaned_at
+         FROM loans l
+         JOIN items i ON i.id = l.item_id
+         WHERE l.returned_at IS NULL AND i.deleted_at IS NULL AND (?1 IS NULL OR l.item_id = ?1)
+         ORDER BY l.due_on IS NULL, l.due_on, l.loaned_at",
+    )?;
+    let rows = stmt.query_map(params![item_id], |row| {
+        Ok(Loan {
+            id: row.get(0)?,
+            item_id: row.get(1)?,
+            item_name: row.get(2)?,
+            unit: row.get(3)?,
+            borrower: row.get(4)?,
+            quantity: row.get(5)?,
+            due_on: row.get(6)?,
+            loaned_at: row.get(7)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Lends part of an item's stock: the quantity leaves the shelf through the
+/// ledger and comes back when the loan is returned.
+async fn handle_lend_item(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(form): Form<LoanForm>,
+) -> Result<Redirect, AppError> {
+    let borrower = form.borrower.trim().to_string();
+    if borrower.is_empty() {
+        return Err(AppError::BadRequest("Say who is borrowing it.".to_string()));
+    }
+    let quantity = match form.quantity.trim().parse::<f64>() {
+        Ok(q) if q.is_finite() && q > 0.0 => q,
+        _ => {
+            return Err(AppError::BadRequest(
+                "Quantity must be a number greater than 0.".to_string(),
+            ));
+        }
+    };
+    let due_on = match form.due_on.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(raw) => Some(parse_date(raw).ok_or_else(|| {
+            AppError::BadRequest("Due date must look like 2027-03-31, or be blank.".to_string())
+        })?),
+    };
+
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            lend_item(&tx, id, &borrower, quantity, due_on.as_deref())?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to(&format!("/items/{id}/edit")))
+}
+
+/// Closes a loan and puts its quantity back on the item.
+async fn handle_return_loan(
+    State(state): State<AppState>,
+    Path(loan_id): Path<i64>,
+) -> Result<Redirect, AppError> {
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            return_loan(&tx, loan_id)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to("/loans"))
+}
+
+/// Records the loan and takes `quantity` off the shelf through the ledger.
+fn lend_item(
+    tx: &Transaction,
+    id: i64,
+    borrower: &str,
+    quantity: f64,
+    due_on: Option<&str>,
+) -> Result<(), AppError> {
+    let item = load_item(tx, id)?;
+    if item.quantity - quantity < -QUANTITY_EPSILON {
+        return Err(AppError::BadRequest(format!(
+            "Only {} on hand, so {} can't be lent.",
+            format_amount(item.quantity, &item.unit, &item.name),
+            format_quantity(quantity, &item.unit)
+        )));
+    }
+
+    let before = item_snapshot(tx, id)?;
+    tx.execute(
+        "INSERT INTO loans (item_id, borrower, quantity, due_on) VALUES (?1, ?2, ?3, ?4)",
+        params![id, borrower, quantity, due_on],
+    )?;
+    tx.execute(
+        "UPDATE items SET quantity = quantity - ?1 WHERE id = ?2",
+        params![quantity, id],
+    )?;
+    record_movement(tx, id, -quantity, "loaned", Some(borrower))?;
+    let after = item_snapshot(tx, id)?;
+    record_event(
+        tx,
+        EntityKind::Item,
+        id,
+        EventAction::QuantityChange,
+        before,
+        after,
+        EventSource::WebForm,
+    )?;
+    Ok(())
+}
+
+/// Closes an open loan and puts its quantity back on the shelf.
+fn return_loan(tx: &Transaction, loan_id: i64) -> Result<(), AppError> {
+    let (item_id, borrower, quantity): (i64, String, f64) = tx
+        .query_row(
+            "SELECT item_id, borrower, quantity FROM loans
+             WHERE id = ?1 AND returned_at IS NULL",
+            params![loan_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Open loan #{loan_id}")))?;
+    load_item(tx, item_id)?;
+
+    let before = item_snapshot(tx, item_id)?;
+    tx.execute(
+        "UPDATE loans SET returned_at = datetime('now') WHERE id = ?1",
+        params![loan_id],
+    )?;
+    tx.execute(
+        "UPDATE items SET quantity = quantity + ?1 WHERE id = ?2",
+        params![quantity, item_id],
+    )?;
+    record_movement(tx, item_id, quantity, "returned", Some(&borrower))?;
+    let after = item_snapshot(tx, item_id)?;
+    record_event(
+        tx,
+        EntityKind::Item,
+        item_id,
+        EventAction::QuantityChange,
+        before,
+        after,
+        EventSource::WebForm,
+    )?;
+    Ok(())
+}
+
+async fn show_loans(State(state): State<AppState>) -> Result<Html<String>, AppError> {
+    let (loans, today) = state
+        .with_db(|conn| Ok((load_loans(conn, None)?, today(conn)?)))
+        .await?;
+
+    let is_overdue = |l: &Loan| l.due_on.as_deref().is_some_and(|d| d < today.as_str());
+    let (overdue, outstanding): (Vec<&Loan>, Vec<&Loan>) =
+        loans.iter().partition(|l| is_overdue(l));
+
+    let mut body =
+        String::from(r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Loans</h1>"#);
+    if loans.is_empty() {
+        body.push_str("<p><em>Nothing is out on loan. Lend an item from its edit page.</em></p>");
+    }
+    for (title, group) in [("Overdue", &overdue), ("Outstanding", &outstanding)] {
+        if group.is_empty() {
+            continue;
+        }
+        body.push_str(&format!(
+            r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">{title}</h2>"#
+        ));
+        body.push_str(&render_loan_table(group, title == "Overdue"));
+    }
+    body.push_str(r#"<p style="margin-top: 1rem;"><a href="/items">Back to Trove</a></p>"#);
+
+    Ok(Html(render_page("Loans", &body)))
+}
+
+fn render_loan_table(loans: &[&Loan], overdue: bool) -> String {
+    let mut html = String::from(
+        r#"<table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#,
+    );
+    for l in loans {
+        let due = match l.due_on.as_deref() {
+            Some(d) if overdue => format!(
+                r#"<span style="color: darkred;">due {}</span>"#,
+                html_escape(d)
+            ),
+            Some(d) => format!("due {}", html_escape(d)),
+            None => "no due date".to_string(),
+        };
+        html.push_str(&format!(
+            r#"<tr>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;"><a href="/items/{item_id}/edit">{amount}</a> — {borrower}<br><small style="color: gray;">since {since}</small></td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; white-space: nowrap;">{due}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right;">
+            <form method="post" action="/loans/{id}/return" style="display: inline;"><button type="submit">returned</button></form>
+          </td>
+        </tr>"#,
+            item_id = l.item_id,
+            amount = format_amount(l.quantity, &l.unit, &html_escape(&l.item_name)),
+            borrower = html_escape(&l.borrower),
+            since = html_escape(&l.loaned_at),
+            id = l.id,
+        ));
+    }
+    html.push_str("</tbody></table>");
+    html
+}
+
+fn render_item_loans(id: i64, page: &ItemPage) -> String {
+    let mut html = format!(
+        r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Lend</h2>
+    <form method="post" action="/items/{id}/loan">
+      <label for="loan_borrower">To whom:</label><br>
+      <input id="loan_borrower" name="borrower" type="text" style="width: 100%;" /><br><br>
+      <label for="loan_quantity">{how_many}:</label><br>
+      <input id="loan_quantity" name="quantity" type="number" min="0" step="any" value="1" style="width: 100%;" /><br><br>
+      <label for="loan_due">Due back (optional):</label><br>
+      <input id="loan_due" name="due_on" type="date" style="width: 100%;" /><br><br>
+      <button type="submit">Lend</button>
+    </form>"#,
+        how_many = if page.unit == "each" {
+            "How many".to_string()
+        } else {
+            format!("How much ({})", html_escape(&page.unit))
+        },
+    );
+    if !page.loans.is_empty() {
+        html.push_str(r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">On loan</h2>"#);
+        let loans: Vec<&Loan> = page.loans.iter().collect();
+        html.push_str(&render_loan_table(&loans, false));
+    }
+    html
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LinkKind {
+    /// Needed for the parent to be complete.
+    KitPart,
+    /// Goes with the parent but isn't required.
+    Accessory,
+}
+
+impl LinkKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            LinkKind::KitPart => "kit_part",
+            LinkKind::Accessory => "accessory",
+        }
+    }
+
+    fn parse(s: &str) -> Option<LinkKind> {
+        match s {
+            "kit_part" => Some(LinkKind::KitPart),
+            "accessory" => Some(LinkKind::Accessory),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            LinkKind::KitPart => "part",
+            LinkKind::Accessory => "accessory",
+        }
+    }
+}
+
+impl rusqlite::types::FromSql for LinkKind {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let s = value.as_str()?;
+        LinkKind::parse(s).ok_or_else(|| {
+            rusqlite::types::FromSqlError::Other(format!("unknown link kind {s:?}").into())
+        })
+    }
+}
+
+/// A child item of a kit, with what's needed to tell whether it's there.
+#[derive(Debug)]
+struct KitPart {
+    kind: LinkKind,
+    needed: f64,
+    item_id: i64,
+    name: String,
+    unit: String,
+    quantity: f64,
+    container_name: Option<String>,
+    location: Option<String>,
+    in_trash: bool,
+    on_loan_to: Option<String>,
+    /// Most recent ledger entry: delta, reason, who.
+    last_movement: Option<(f64, String, Option<String>)>,
+}
+
+enum PartStatus {
+    Present,
+    InTrash,
+    OnLoan(String),
+    CheckedOut(Option<String>),
+    Missing,
+}
+
+impl KitPart {
+    fn status(&self) -> PartStatus {
+        if self.in_trash {
+            return PartStatus::InTrash;
+        }
+        if self.quantity + QUANTITY_EPSILON >= self.needed {
+            return PartStatus::Present;
+        }
+        if let Some(ref who) = self.on_loan_to {
+            return PartStatus::OnLoan(who.clone());
+        }
+        match &self.last_movement {
+            Some((delta, reason, who))
+                if *delta < 0.0
+                    && reason != "count corrected"
+                    && !reason.starts_with("unit changed") =>
+            {
+                PartStatus::CheckedOut(who.clone())
+            }
+            _ => PartStatus::Missing,
+        }
+    }
+}
+
+fn load_kit_parts(conn: &Connection, parent_id: i64) -> rusqlite::Result<Vec<KitPart>> {
+    let mut stmt = conn.prepare(
+        "SELECT l.kind, l.quantity, i.id, i.name, i.unit, i.quantity, c.name, il.name,
+                i.deleted_at IS NOT NULL,
+                (SELECT group_concat(borrower, ', ') FROM loans
+                 WHERE item_id = i.id AND returned_at IS NULL),
+                m.delta, m.reason, m.who
+         FROM item_links l
+         JOIN items i ON i.id = l.child_id
+         LEFT JOIN containers c ON c.id = i.container_id
+         LEFT JOIN item_locations il ON il.item_id = i.id
+         LEFT JOIN stock_movements m
+             ON m.id = (SELECT max(id) FROM stock_movements WHERE item_id = i.id)
+         WHERE l.parent_id = ?1
+         ORDER BY l.kind DESC, i.name COLLATE NOCASE",
+    )?;
+    let rows = stmt.query_map(params![parent_id], |row| {
+        let delta: Option<f64> = row.get(10)?;
+        Ok(KitPart {
+            kind: row.get(0)?,
+            needed: row.get(1)?,
+            item_id: row.get(2)?,
+            name: row.get(3)?,
+            unit: row.get(4)?,
+            quantity: row.get(5)?,
+            container_name: row.get(6)?,
+            location: row.get(7)?,
+            in_trash: row.get(8)?,
+            on_loan_to: row.get(9)?,
+            last_movement: match delta {
+                Some(delta) => Some((delta, row.get(11)?, row.get(12)?)),
+                None => None,
+            },
+        })
+    })?;
+    rows.collect()
+}
+
+fn load_part_of(
+    conn: &Connection,
+    child_id: i64,
+) -> rusqlite::Result<Vec<(i64, String, LinkKind)>> {
+    let mut stmt = conn.prepare(
+        "SELECT i.id, i.name, l.kind
+         FROM item_links l
+         JOIN items i ON i.id = l.parent_id
+         WHERE l.child_id = ?1 AND i.deleted_at IS NULL
+         ORDER BY i.name COLLATE NOCASE",
+    )?;
+    let rows = stmt.query_map(params![child_id], |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    })?;
+    rows.collect()
+}
+
+fn load_item_choices(conn: &Connection, except: i64) -> rusqlite::Result<Vec<(i64, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name FROM items
+         WHERE deleted_at IS NULL AND id <> ?1
+         ORDER BY name COLLATE NOCASE, id",
+    )?;
+    let rows = stmt.query_map(params![except], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+#[derive(Deserialize)]
+struct LinkForm {
+    child_id: String,
+    kind: String,
+    quantity: Option<String>,
+}
+
+async fn handle_add_link(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(form): Form<LinkForm>,
+) -> Result<Redirect, AppError> {
+    let child_id: i64 = form
+        .child_id
+        .parse()
+        .map_err(|_| AppError::BadRequest("Choose an item to link.".to_string()))?;
+    let kind = LinkKind::parse(&form.kind)
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown link kind {:?}.", form.kind)))?;
+    let needed = match form.quantity.as_deref().map(str::trim) {
+        None | Some("") => 1.0,
+        Some(raw) => match raw.parse::<f64>() {
+            Ok(q) if q.is_finite() && q > 0.0 => q,
+            _ => {
+                return Err(AppError::BadRequest(
+                    "Quantity needed must be a number greater than 0.".to_string(),
+                ));
+            }
+        },
+    };
+    if child_id == id {
+        return Err(AppError::BadRequest(
+            "An item can't be part of itself.".to_string(),
+        ));
+    }
+
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            load_item(&tx, id)?;
+            let child = load_item(&tx, child_id)?;
+
+            // Refuse links that would make the parent a part of itself.
+            let cycle: bool = tx.query_row(
+                "WITH RECURSIVE below(id) AS (
+                     SELECT child_id FROM item_links WHERE parent_id = ?1
+                     UNION
+                     SELECT l.child_id FROM item_links l JOIN below b ON l.parent_id = b.id
+                 )
+                 SELECT EXISTS (SELECT 1 FROM below WHERE id = ?2)",
+                params![child_id, id],
+                |row| row.get(0),
+            )?;
+            if cycle {
+                return Err(AppError::BadRequest(format!(
+                    "{} already contains this item, so it can't also be one of its parts.",
+                    child.name
+                )));
+            }
+
+            let before = item_snapshot(&tx, id)?;
+            tx.execute(
+                "INSERT INTO item_links (parent_id, child_id, kind, quantity)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (parent_id, child_id) DO UPDATE
+                 SET kind = excluded.kind, quantity = excluded.quantity",
+                params![id, child_id, kind.as_str(), needed],
+            )?;
+            let after = item_snapshot(&tx, id)?;
+            if after != before {
+                record_event(
+                    &tx,
+                    EntityKind::Item,
+                    id,
+                    EventAction::Update,
+                    before,
+                    after,
+                    EventSource::WebForm,
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to(&format!("/items/{id}/edit")))
+}
+
+async fn handle_remove_link(
+    State(state): State<AppState>,
+    Path((id, child_id)): Path<(i64, i64)>,
+) -> Result<Redirect, AppError> {
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            load_item(&tx, id)?;
+            let before = item_snapshot(&tx, id)?;
+            let removed = tx.execute(
+                "DELETE FROM item_links WHERE parent_id = ?1 AND child_id = ?2",
+                params![id, child_id],
+            )?;
+            if removed > 0 {
+                let after = item_snapshot(&tx, id)?;
+                record_event(
+                    &tx,
+                    EntityKind::Item,
+                    id,
+                    EventAction::Update,
+                    before,
+                    after,
+                    EventSource::WebForm,
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to(&format!("/items/{id}/edit")))
+}
+
+fn render_item_links(id: i64, page: &ItemPage) -> String {
+    let mut html = String::from(r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Kit</h2>"#);
+
+    if !page.part_of.is_empty() {
+        let parents: Vec<String> = page
+            .part_of
+            .iter()
+            .map(|(pid, name, kind)| {
+                format!(
+                    r#"{} of <a href="/items/{pid}/kit">{}</a>"#,
+                    kind.label(),
+                    html_escape(name)
+                )
+            })
+            .collect();
+        html.push_str(&format!("<p>This is a {}.</p>", parents.join(", ")));
+    }
+
+    if !page.parts.is_empty() {
+        html.push_str(&format!(
+            r#"<p><a href="/items/{id}/kit">Kit page</a> — {}</p>"#,
+            render_kit_verdict(&page.parts)
+        ));
+        html.push_str(
+            r#"<table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#,
+        );
+        for p in &page.parts {
+            html.push_str(&format!(
+                r#"<tr>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;"><a href="/items/{child}/edit">{name}</a> <small style="color: gray;">{kind}, needs {needed}</small></td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right;">
+            <form method="post" action="/items/{id}/links/{child}/delete" style="display: inline;"><button type="submit">unlink</button></form>
+          </td>
+        </tr>"#,
+                child = p.item_id,
+                name = html_escape(&p.name),
+                kind = p.kind.label(),
+                needed = html_escape(&format_quantity(p.needed, &p.unit)),
+            ));
+        }
+        html.push_str("</tbody></table>");
+    }
+
+    if page.item_choices.is_empty() {
+        return html;
+    }
+    html.push_str(&format!(
+        r#"<form method="post" action="/items/{id}/links" style="margin-top: 0.5rem;">
+      <label for="link_child">Add a part or accessory:</label><br>
+      <select id="link_child" name="child_id" style="width: 100%;">"#
+    ));
+    for (cid, name) in &page.item_choices {
+        html.push_str(&format!(
+            r#"<option value="{cid}">{} (#{cid})</option>"#,
+            html_escape(name)
+        ));
+    }
+    html.push_str(
+        r#"</select><br><br>
+      <label for="link_kind">It is a:</label><br>
+      <select id="link_kind" name="kind" style="width: 100%;">
+        <option value="kit_part">part of this kit (needed)</option>
+        <option value="accessory">accessory (optional)</option>
+      </select><br><br>
+      <label for="link_quantity">How many the kit needs:</label><br>
+      <input id="link_quantity" name="quantity" type="number" min="0" step="any" value="1" style="width: 100%;" /><br><br>
+      <button type="submit">Link</button>
+    </form>"#,
+    );
+
+    html
+}
+
+/// "Kit complete" or how many required parts are short.
+fn render_kit_verdict(parts: &[KitPart]) -> String {
+    let short = parts
+        .iter()
+        .filter(|p| p.kind == LinkKind::KitPart && !matches!(p.status(), PartStatus::Present))
+        .count();
+    match short {
+        0 => r#"<strong style="color: darkgreen;">kit complete</strong>"#.to_string(),
+        1 => {
+            r#"<strong style="color: darkred;">1 part missing or checked out</strong>"#.to_string()
+        }
+        n => {
+            format!(r#"<strong style="color: darkred;">{n} parts missing or checked out</strong>"#)
+        }
+    }
+}
+
+async fn show_kit(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Html<String>, AppError> {
+    let (item, container, parts) = state
+        .with_db(move |conn| {
+            let item = load_item(conn, id)?;
+            let container = match item.container_id {
+                Some(cid) => Some(load_container(conn, cid)?.name),
+                None => None,
+            };
+            Ok((item, container, load_kit_parts(conn, id)?))
+        })
+        .await?;
+
+    let place = |container: Option<&str>, location: Option<&str>| -> String {
+        match (container, location) {
+            (Some(c), Some(l)) => format!("{} — {}", html_escape(c), html_escape(l)),
+            (Some(c), None) => html_escape(c),
+            (None, Some(l)) => html_escape(l),
+            (None, None) => r#"<span style="color: gray;">no container</span>"#.to_string(),
+        }
+    };
+
+    let mut body = format!(
+        r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">{name}</h1>
+    <p>{place}</p>"#,
+        name = html_escape(&item.name),
+        place = place(container.as_deref(), item.location.as_deref()),
+    );
+
+    if parts.is_empty() {
+        body.push_str(&format!(
+            r#"<p><em>No parts linked yet. Add them from the <a href="/items/{id}/edit">item page</a>.</em></p>"#
+        ));
+    } else {
+        body.push_str(&format!("<p>{}</p>", render_kit_verdict(&parts)));
+        body.push_str(
+            r#"<table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#,
+        );
+        for p in &parts {
+            let status = match p.status() {
+                PartStatus::Present => r#"<span style="color: darkgreen;">here</span>"#.to_string(),
+                PartStatus::InTrash => {
+                    r#"<span style="color: darkred;">in the trash</span>"#.to_string()
+                }
+                PartStatus::OnLoan(who) => format!(
+                    r#"<span style="color: darkorange;">on loan to {}</span>"#,
+                    html_escape(&who)
+                ),
+                PartStatus::CheckedOut(who) => format!(
+                    r#"<span style="color: darkorange;">checked out{}</span>"#,
+                    who.map(|w| format!(" by {}", html_escape(&w)))
+                        .unwrap_or_default()
+                ),
+                PartStatus::Missing => {
+                    r#"<span style="color: darkred;">missing</span>"#.to_string()
+                }
+            };
+            let link = if p.in_trash {
+                "/trash".to_string()
+            } else {
+                format!("/items/{}/edit", p.item_id)
+            };
+            body.push_str(&format!(
+                r#"<tr>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;"><a href="{link}">{name}</a> <small style="color: gray;">{kind}</small><br><small>{place}</small></td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; white-space: nowrap;">{have} of {needed}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right;">{status}</td>
+        </tr>"#,
+                name = html_escape(&p.name),
+                kind = p.kind.label(),
+                place = place(p.container_name.as_deref(), p.location.as_deref()),
+                have = html_escape(&format_number(p.quantity)),
+                needed = html_escape(&format_quantity(p.needed, &p.unit)),
+            ));
+        }
+        body.push_str("</tbody></table>");
+    }
+    body.push_str(&format!(
+        r#"<p style="margin-top: 1rem;"><a href="/items/{id}/edit">Edit item</a> · <a href="/items">Back to Trove</a></p>"#
+    ));
+
+    Ok(Html(render_page(&format!("Kit: {}", item.name), &body)))
+}
+
+/// One item on the valuation report.
+struct ValuationRow {
+    row: ItemWithContainer,
+    purchase: Purchase,
+    /// Full path of the item's container, as in breadcrumbs.
+    container_path: Option<String>,
+}
+
+impl ValuationRow {
+    /// Price times quantity, or `None` if no price was recorded.
+    fn value(&self) -> Option<f64> {
+        let item = &self.row.item;
+        self.purchase.price.map(|p| p * (item.quantity + item.lent))
+    }
+
+    fn location(&self) -> &str {
+        self.row.item.location.as_deref().unwrap_or("No location")
+    }
+
+    fn container(&self) -> &str {
+        self.container_path.as_deref().unwrap_or("Loose")
+    }
+}
+
+/// Every live item with its purchase details, ordered by location, then
+/// container, then name.
+fn load_valuation(conn: &Connection) -> Result<Vec<ValuationRow>, AppError> {
+    let mut purchases: HashMap<i64, Purchase> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT id, purchase_price, purchase_date, serial_number, receipt_file
+         FROM items WHERE deleted_at IS NULL",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            Purchase {
+                price: row.get(1)?,
+                date: row.get(2)?,
+                serial_number: row.get(3)?,
+                receipt_file: row.get(4)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (id, purchase) = row?;
+        purchases.insert(id, purchase);
+    }
+
+    let paths = load_container_paths(conn)?;
+    let path_of = |id: Option<i64>| id.and_then(|id| paths.get(&id));
+    let mut report: Vec<ValuationRow> = load_items_from_db(conn, None)?
+        .into_iter()
+        .map(|row| ValuationRow {
+            purchase: purchases.remove(&row.item.id).unwrap_or_default(),
+            container_path: path_of(row.item.container_id).map(|p| path_label(p)),
+            row,
+        })
+        .collect();
+    report.sort_by_cached_key(|r| {
+        (
+            r.row.item.location.is_none(),
+            r.location().to_lowercase(),
+            r.row.item.container_id.is_none(),
+            path_of(r.row.item.container_id)
+                .map(|p| path_sort_key(p))
+                .unwrap_or_default(),
+            r.row.item.container_id,
+            r.row.item.name.to_lowercase(),
+        )
+    });
+    Ok(report)
+}
+
+fn format_money(amount: f64) -> String {
+    // An empty float sum is -0.0; adding zero drops the sign.
+    format!("{:.2}", amount + 0.0)
+}
+
+async fn show_valuation(State(state): State<AppState>) -> Result<Html<String>, AppError> {
+    let report = state.with_db(|conn| load_valuation(conn)).await?;
+
+    let total: f64 = report.iter().filter_map(ValuationRow::value).sum();
+    let unpriced = report.iter().filter(|r| r.purchase.price.is_none()).count();
+
+    let mut body = String::from(
+        r#"<style>@media print { form, .no-print { display: none; } a { color: inherit; text-decoration: none; } }</style>
+    <h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Valuation</h1>"#,
+    );
+    body.push_str(&format!(
+        r#"<p><strong>Total: {}</strong> across {} items{}.</p>
+    <p class="no-print"><a href="/reports/valuation.csv">Download CSV</a> · <a href="javascript:window.print()">Print</a></p>"#,
+        format_money(total),
+        report.len(),
+        if unpriced > 0 {
+            format!(" ({unpriced} without a price)")
+        } else {
+            String::new()
+        },
+    ));
+
+    let cell = "padding: 2px 4px; border-top: 1px solid #eee;";
+    let mut i = 0;
+    while i < report.len() {
+        let location = report[i].location();
+        let end = report[i..]
+            .iter()
+            .position(|r| !r.location().eq_ignore_ascii_case(location))
+            .map_or(report.len(), |n| i + n);
+        let group = &report[i..end];
+        let location_total: f64 = group.iter().filter_map(ValuationRow::value).sum();
+        body.push_str(&format!(
+            r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">{} — {}</h2>"#,
+            html_escape(location),
+            format_money(location_total)
+        ));
+
+        let mut j = 0;
+        while j < group.len() {
+            let container_id = group[j].row.item.container_id;
+            let container_end = group[j..]
+                .iter()
+                .position(|r| r.row.item.container_id != container_id)
+                .map_or(group.len(), |n| j + n);
+            let rows = &group[j..container_end];
+            let container_total: f64 = rows.iter().filter_map(ValuationRow::value).sum();
+            body.push_str(&format!(
+                r#"<h3 style="font-size: 1rem; margin: 0.75rem 0 0.25rem;">{} — {}</h3>
+    <table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#,
+                html_escape(group[j].container()),
+                format_money(container_total)
+            ));
+            for r in rows {
+                let item = &r.row.item;
+                let mut details = Vec::new();
+                if let Some(ref serial) = r.purchase.serial_number {
+                    details.push(format!("S/N {}", html_escape(serial)));
+                }
+                if let Some(ref date) = r.purchase.date {
+                    details.push(format!("bought {}", html_escape(date)));
+                }
+                if let Some(price) = r.purchase.price {
+                    details.push(format!(
+                        "{} per {}",
+                        format_money(price),
+                        if item.unit == "each" {
+                            "item"
+                        } else {
+                            &item.unit
+                        }
+                    ));
+                }
+                if let Some(ref receipt) = r.purchase.receipt_file {
+                    details.push(format!(
+                        r#"<a href="/media/{}">receipt</a>"#,
+                        html_escape(receipt)
+                    ));
+                }
+                body.push_str(&format!(
+                    r#"<tr>
+          <td style="{cell} width: 4rem;">{thumb}</td>
+          <td style="{cell}"><a href="/items/{id}/edit">{amount}</a><br><small style="color: gray;">{details}</small>{attributes}</td>
+          <td style="{cell} text-align: right; white-space: nowrap;">{value}</td>
+        </tr>"#,
+                    thumb = r
+                        .row
+                        .thumb
+                        .as_deref()
+                        .map(|t| render_thumb(t, "3.5rem"))
+                        .unwrap_or_default(),
+                    id = item.id,
+                    amount = format_amount(item.quantity, &item.unit, &html_escape(&item.name)),
+                    details = details.join(" · "),
+                    attributes = render_attribute_summary(&r.row.attributes),
+                    value = r.value().map(format_money).unwrap_or_else(|| "—".to_string()),
+                ));
+            }
+            body.push_str("</tbody></table>");
+            j = container_end;
+        }
+        i = end;
+    }
+
+    body.push_str(
+        r#"<p class="no-print" style="margin-top: 1rem;"><a href="/items">Back to Trove</a></p>"#,
+    );
+
+    Ok(Html(render_page("Valuation", &body)))
+}
+
+async fn export_valuation_csv(State(state): State<AppState>) -> Result<Response, AppError> {
+    let report = state.with_db(|conn| load_valuation(conn)).await?;
+
+    let mut csv = String::from(
+        "id,name,quantity,unit,location,container,purchase_price,purchase_date,serial_number,value,tags,attributes,receipt\r\n",
+    );
+    for r in &report {
+        let item = &r.row.item;
+        let attributes: Vec<String> = r
+            .row
+            .attributes
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect();
+        let fields = [
+            item.id.to_string(),
+            item.name.clone(),
+            format_number(item.quantity),
+            item.unit.clone(),
+            item.location.clone().unwrap_or_default(),
+            r.container_path.clone().unwrap_or_default(),
+            r.purchase.price.map(format_money).unwrap_or_default(),
+            r.purchase.date.clone().unwrap_or_default(),
+            r.purchase.serial_number.clone().unwrap_or_default(),
+            r.value().map(format_money).unwrap_or_default(),
+            r.row.tags.join(", "),
+            attributes.join("; "),
+            r.purchase.receipt_file.clone().unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&line.join(","));
+        csv.push_str("\r\n");
+    }
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"trove-valuation.csv\"",
+            ),
+        ],
+        csv,
+    )
+        .into_response())
+}
+
+/// Quotes a CSV field when it holds a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Items expiring within this many days are flagged as soon.
+const EXPIRING_SOON_DAYS: i64 = 30;
+
+/// Accepts YYYY-MM-DD for a real calendar date and returns it unchanged.
+fn parse_date(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let mut parts = raw.splitn(3, '-');
+    let (y, m, d) = (parts.next()?, parts.next()?, parts.next()?);
+    if y.len() != 4 || m.len() != 2 || d.len() != 2 {
+        return None;
+    }
+    // `parse` alone would let a sign through ("2026-+1-01").
+    if ![y, m, d]
+        .iter()
+        .all(|part| part.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return None;
+    }
+    let (year, month, day): (u32, u32, u32) = (y.parse().ok()?, m.parse().ok()?, d.parse().ok()?);
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    (1..=days_in_month).contains(&day).then(|| raw.to_string())
+}
+
+/// Today's local date as YYYY-MM-DD, from SQLite so it matches stored dates.
+fn today(conn: &Connection) -> rusqlite::Result<String> {
+    conn.query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+}
+
+/// Small "exp" note for item lists, red once the date has passed.
+fn render_expiry(date: &str, today: &str) -> String {
+    let color = if date < today { "darkred" } else { "gray" };
+    format!(
+        r#" <small style="color: {color};">exp {}</small>"#,
+        html_escape(date)
+    )
+}
+
+async fn show_expiring(State(state): State<AppState>) -> Result<Html<String>, AppError> {
+    let (items, today, soon) = state
+        .with_db(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                r#"{ITEM_LIST_SELECT}
+                WHERE i.deleted_at IS NULL AND i.expires_on IS NOT NULL
+                ORDER BY i.expires_on, i.name COLLATE NOCASE
+                "#
+            ))?;
+            let items = stmt
+                .query_map([], item_list_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let soon: String = conn.query_row(
+                "SELECT date('now', 'localtime', ?1)",
+                params![format!("+{EXPIRING_SOON_DAYS} days")],
+                |row| row.get(0),
+            )?;
+            Ok((items, today(conn)?, soon))
+        })
+        .await?;
+
+    let mut body =
+        String::from(r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Expiring</h1>"#);
+    if items.is_empty() {
+        body.push_str(
+            "<p><em>No items have an expiry date. Add one on an item's edit page.</em></p>",
+        );
+    } else {
+        body.push_str(
+            r#"<table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#,
+        );
+        for row in &items {
+            let item = &row.item;
+            let date = item.expires_on.as_deref().unwrap_or_default();
+            let status = if date < today.as_str() {
+                r#"<span style="color: darkred;">expired</span>"#
+            } else if date <= soon.as_str() {
+                r#"<span style="color: darkorange;">soon</span>"#
+            } else {
+                ""
+            };
+            let place = row
+                .container_name
+                .as_deref()
+                .or(item.location.as_deref())
+                .map(|p| format!(" — {}", html_escape(p)))
+                .unwrap_or_default();
+            body.push_str(&format!(
+                r#"<tr>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; white-space: nowrap;">{date}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;"><a href="/items/{id}/edit">{amount}</a>{place}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right;">{status}</td>
+        </tr>"#,
+                date = html_escape(date),
+                id = item.id,
+                amount = format_amount(item.quantity, &item.unit, &html_escape(&item.name)),
+            ));
+        }
+        body.push_str("</tbody></table>");
+    }
+    body.push_str(r#"<p style="margin-top: 1rem;"><a href="/items">Back to Trove</a></p>"#);
+
+    Ok(Html(render_page("Expiring", &body)))
+}
+
+/// An item at or below its threshold, as shown on the shopping list.
+struct ShoppingItem {
+    item: Item,
+    container_name: Option<String>,
+}
+
+/// Low items grouped by location, unplaced ones under "No location".
+fn load_shopping_list(conn: &Connection) -> rusqlite::Result<Vec<(String, Vec<ShoppingItem>)>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"{ITEM_LIST_SELECT}
+        WHERE i.deleted_at IS NULL
+          AND i.min_quantity IS NOT NULL
+          AND i.quantity + {LENT_SQL} <= i.min_quantity + {QUANTITY_EPSILON}
+        ORDER BY il.name IS NULL, il.name COLLATE NOCASE, i.name COLLATE NOCASE
+        "#
+    ))?;
+    let rows = stmt.query_map([], item_list_row)?;
+
+    let mut groups: Vec<(String, Vec<ShoppingItem>)> = Vec::new();
+    for row in rows {
+        let row = row?;
+        let location = row
+            .item
+            .location
+            .clone()
+            .unwrap_or_else(|| "No location".to_string());
+        let entry = ShoppingItem {
+            item: row.item,
+            container_name: row.container_name,
+        };
+        match groups.last_mut() {
+            Some((last, items)) if last.eq_ignore_ascii_case(&location) => items.push(entry),
+            _ => groups.push((location, vec![entry])),
+        }
+    }
+    Ok(groups)
+}
+
+async fn show_shopping_list(State(state): State<AppState>) -> Result<Html<String>, AppError> {
+    let groups = state.with_db(|conn| Ok(load_shopping_list(conn)?)).await?;
+    Ok(Html(render_page(
+        "Shopping list",
+        &render_shopping_list(&groups, None),
+    )))
+}
+
+async fn handle_print_shopping_list(
+    State(state): State<AppState>,
+) -> Result<Html<String>, AppError> {
+    let groups = state.with_db(|conn| Ok(load_shopping_list(conn)?)).await?;
+    if groups.is_empty() {
+        return Err(AppError::BadRequest(
+            "Nothing is running low, so there is nothing to print.".to_string(),
+        ));
+    }
+
+    let outcome = match state.print_zpl(shopping_list_zpl(&groups)).await {
+        Ok(()) => "Sent to the label printer.".to_string(),
+        Err(e) => {
+            eprintln!("Failed to print shopping list: {e}");
+            format!("The list did not print: {e}")
+        }
+    };
+    Ok(Html(render_page(
+        "Shopping list",
+        &render_shopping_list(&groups, Some(&outcome)),
+    )))
+}
+
+fn render_shopping_list(groups: &[(String, Vec<ShoppingItem>)], notice: Option<&str>) -> String {
+    let mut body = String::from(
+        r#"<style>@media print { form, .no-print { display: none; } }</style>
+    <h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Shopping list</h1>"#,
+    );
+    if let Some(notice) = notice {
+        body.push_str(&format!("<p><strong>{}</strong></p>", html_escape(notice)));
+    }
+
+    if groups.is_empty() {
+        body.push_str(
+            "<p><em>Nothing is at or below its keep-at-least amount. Set one on an item's edit page.</em></p>",
+        );
+    }
+
+    for (location, items) in groups {
+        body.push_str(&format!(
+            r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">{}</h2>
+    <table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#,
+            html_escape(location)
+        ));
+        for s in items {
+            let item = &s.item;
+            let min = item.min_quantity.unwrap_or_default();
+            let container = s
+                .container_name
+                .as_deref()
+                .map(|c| format!(" — {}", html_escape(c)))
+                .unwrap_or_default();
+            body.push_str(&format!(
+                r#"<tr>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;"><a href="/items/{id}/edit">{name}</a>{container}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right; white-space: nowrap;">have {have}, keep {min}</td>
+        </tr>"#,
+                id = item.id,
+                name = html_escape(&item.name),
+                have = html_escape(&format_quantity(item.quantity, &item.unit)),
+                min = html_escape(&format_quantity(min, &item.unit)),
+            ));
+        }
+        body.push_str("</tbody></table>");
+    }
+
+    if !groups.is_empty() {
+        body.push_str(
+            r#"<form method="post" action="/shopping-list/print" style="margin-top: 1rem;">
+      <button type="submit">Print on label printer</button>
+      <button type="button" onclick="window.print()">Print this page</button>
+    </form>"#,
+        );
+    }
+    body.push_str(
+        r#"<p class="no-print" style="margin-top: 1rem;"><a href="/items">Back to Trove</a></p>"#,
+    );
+
+    body
+}
+
+#[derive(Debug)]
+struct Movement {
+    delta: f64,
+    reason: String,
+    who: Option<String>,
+    created_at: String,
+}
+
+#[derive(Deserialize)]
+struct MovementForm {
+    quantity: String,
+    reason: Option<String>,
+    who: Option<String>,
+}
+
+async fn handle_check_out(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(form): Form<MovementForm>,
+) -> Result<Redirect, AppError> {
+    apply_movement(&state, id, form, -1.0, "checked out").await
+}
+
+async fn handle_check_in(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(form): Form<MovementForm>,
+) -> Result<Redirect, AppError> {
+    apply_movement(&state, id, form, 1.0, "checked in").await
+}
+
+/// Shared body of check-out (`sign` -1) and check-in (`sign` 1). Amounts
+/// are in the item's own unit.
+async fn apply_movement(
+    state: &AppState,
+    id: i64,
+    form: MovementForm,
+    sign: f64,
+    default_reason: &'static str,
+) -> Result<Redirect, AppError> {
+    let amount = match form.quantity.trim().parse::<f64>() {
+        Ok(q) if q.is_finite() && q > 0.0 => q,
+        _ => {
+            return Err(AppError::BadRequest(
+                "Quantity must be a number greater than 0.".to_string(),
+            ));
+        }
+    };
+    let reason = normalize_optional(form.reason).unwrap_or_else(|| default_reason.to_string());
+    let who = normalize_optional(form.who);
+
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let item = load_item(&tx, id)?;
+            let delta = sign * amount;
+            if item.quantity + delta < -QUANTITY_EPSILON {
+                return Err(AppError::BadRequest(format!(
+                    "Only {} on hand, so {} can't be checked out.",
+                    format_amount(item.quantity, &item.unit, &item.name),
+                    format_quantity(amount, &item.unit)
+                )));
+            }
+
+            let before = item_snapshot(&tx, id)?;
+            tx.execute(
+                "UPDATE items SET quantity = quantity + ?1 WHERE id = ?2",
+                params![delta, id],
+            )?;
+            record_movement(&tx, id, delta, &reason, who.as_deref())?;
+            let after = item_snapshot(&tx, id)?;
+            record_event(
+                &tx,
+                EntityKind::Item,
+                id,
+                EventAction::QuantityChange,
+                before,
+                after,
+                EventSource::WebForm,
+            )?;
+
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to(&format!("/items/{id}/edit")))
+}
+
+fn render_movements(id: i64, page: &ItemPage) -> String {
+    let mut html = format!(
+        r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Check out / check in</h2>
+    <form method="post" action="/items/{id}/checkout">
+      <label for="move_quantity">{how_many}:</label><br>
+      <input id="move_quantity" name="quantity" type="number" min="0" step="any" value="1" style="width: 100%;" /><br><br>
+      <label for="move_reason">Reason (optional):</label><br>
+      <input id="move_reason" name="reason" type="text" style="width: 100%;" /><br><br>
+      <label for="move_who">Who (optional):</label><br>
+      <input id="move_who" name="who" type="text" style="width: 100%;" /><br><br>
+      <button type="submit" formaction="/items/{id}/checkout">Check out</button>
+      <button type="submit" formaction="/items/{id}/checkin">Check in</button>
+    </form>
+    <h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Movements</h2>"#,
+        how_many = if page.unit == "each" {
+            "How many".to_string()
+        } else {
+            format!("How much ({})", html_escape(&page.unit))
+        },
+    );
+
+    let ledger_total: f64 = page.movements.iter().map(|m| m.delta).sum();
+    if (ledger_total - page.quantity).abs() > QUANTITY_EPSILON {
+        html.push_str(&format!(
+            r#"<p style="color: darkred;">The ledger adds up to {}, but the stored quantity is {}.</p>"#,
+            html_escape(&format_quantity(ledger_total, &page.unit)),
+            html_escape(&format_quantity(page.quantity, &page.unit))
+        ));
+    }
+
+    if page.movements.is_empty() {
+        html.push_str("<p><em>No movements yet.</em></p>");
+        return html;
+    }
+
+    html.push_str(
+        r#"<table style="width: 100%; border-collapse: collapse; font-size: 0.85rem;"><tbody>"#,
+    );
+    for m in &page.movements {
+        let who = m
+            .who
+            .as_deref()
+            .map(|w| format!(" — {}", html_escape(w)))
+            .unwrap_or_default();
+        html.push_str(&format!(
+            r#"<tr>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; color: gray;">{when}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right;">{sign}{delta}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;">{reason}{who}</td>
+        </tr>"#,
+            when = html_escape(&m.created_at),
+            sign = if m.delta > 0.0 { "+" } else { "" },
+            delta = format_number(m.delta),
+            reason = html_escape(&m.reason),
+        ));
+    }
+    html.push_str("</tbody></table>");
+
+    html
+}
+
+async fn handle_delete_item(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Redirect, AppError> {
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let before = item_snapshot(&tx, id)?;
+            let changed = tx.execute(
+                "UPDATE items SET deleted_at = datetime('now')
+                 WHERE id = ?1 AND deleted_at IS NULL",
+                params![id],
+            )?;
+            if changed == 0 {
+                return Err(AppError::NotFound(format!("Item #{id}")));
+            }
+            let after = item_snapshot(&tx, id)?;
+            record_event(
+                &tx,
+                EntityKind::Item,
+                id,
+                EventAction::Delete,
+                before,
+                after,
+                EventSource::WebForm,
+            )?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to("/items"))
+}
+
+#[derive(Debug)]
+struct TrashedItem {
+    item: Item,
+    container_name: Option<String>,
+    deleted_at: String,
+}
+
+async fn show_trash(State(state): State<AppState>) -> Result<Html<String>, AppError> {
+    let trashed = state.with_db(|conn| Ok(load_trashed_items(conn)?)).await?;
+
+    let mut body = String::new();
+    body.push_str(r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Trash</h1>"#);
+
+    if trashed.is_empty() {
+        body.push_str("<p><em>The trash is empty.</em></p>\n");
+    } else {
+        body.push_str(
+            r#"<table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#,
+        );
+        for t in &trashed {
+            let mut line = format_amount(t.item.quantity, &t.item.unit, &html_escape(&t.item.name));
+            if let Some(ref name) = t.container_name {
+                line.push_str(&format!(" — {}", html_escape(name)));
+            }
+            if let Some(ref loc) = t.item.location {
+                line.push_str(&format!(" — {}", html_escape(loc)));
+            }
+
+            body.push_str(&format!(
+                r#"<tr>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;">{line}<br><small style="color: gray;">deleted {deleted_at}</small></td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right; white-space: nowrap;">
+            <form method="post" action="/trash/{id}/restore" style="display: inline;"><button type="submit">restore</button></form>
+            <form method="post" action="/trash/{id}/purge" style="display: inline;"><button type="submit">purge</button></form>
+          </td>
+        </tr>"#,
+                id = t.item.id,
+                deleted_at = html_escape(&t.deleted_at),
+            ));
+        }
+        body.push_str("</tbody></table>");
+    }
+
+    body.push_str(r#"<p style="margin-top: 1rem;"><a href="/items">Back to Trove</a></p>"#);
+
+    Ok(Html(render_page("Trash", &body)))
+}
+
+async fn handle_restore_item(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Redirect, AppError> {
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let before = item_snapshot(&tx, id)?;
+            let changed = tx.execute(
+                "UPDATE items SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![id],
+            )?;
+            if changed == 0 {
+                return Err(AppError::NotFound(format!("Trashed item #{id}")));
+            }
+            let after = item_snapshot(&tx, id)?;
+            record_event(
+                &tx,
+                EntityKind::Item,
+                id,
+                EventAction::Restore,
+                before,
+                after,
+                EventSource::WebForm,
+            )?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to("/trash"))
+}
+
+async fn handle_purge_item(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Redirect, AppError> {
+    let media_dir = PathBuf::from(&state.config.media_dir);
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let before = item_snapshot(&tx, id)?;
+            let photos = load_photos(&tx, EntityKind::Item, id)?;
+            let receipt = load_purchase(&tx, id)?.receipt_file;
+            let changed = tx.execute(
+                "DELETE FROM items WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![id],
+            )?;
+            if changed == 0 {
+                return Err(AppError::NotFound(format!("Trashed item #{id}")));
+            }
+            record_event(
+                &tx,
+                EntityKind::Item,
+                id,
+                EventAction::Purge,
+                before,
+                None,
+                EventSource::WebForm,
+            )?;
+            tx.commit()?;
+            remove_photo_files(&media_dir, &photos);
+            if let Some(receipt) = receipt {
+                remove_media_file(&media_dir, &receipt);
+            }
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to("/trash"))
+}
+
+/// A row on the locations page.
+#[derive(Debug)]
+struct LocationSummary {
+    location: Location,
+    /// Live items there, including those that inherit it from a container.
+    item_count: i64,
+    /// Containers set to it directly.
+    container_count: i64,
+}
+
+fn load_location_summaries(conn: &Connection) -> rusqlite::Result<Vec<LocationSummary>> {
+    let mut stmt = conn.prepare(
+        "SELECT l.id, l.name,
+                (SELECT COUNT(*) FROM item_locations il JOIN items i ON i.id = il.item_id
+                 WHERE il.location_id = l.id AND i.deleted_at IS NULL),
+                (SELECT COUNT(*) FROM containers c WHERE c.location_id = l.id)
+         FROM locations l
+         ORDER BY l.name",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(LocationSummary {
+            location: Location {
+                id: row.get(0)?,
+                name: row.get(1)?,
+            },
+            item_count: row.get(2)?,
+            container_count: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+async fn show_locations(State(state): State<AppState>) -> Result<Html<String>, AppError> {
+    let summaries = state
+        .with_db(|conn| Ok(load_location_summaries(conn)?))
+        .await?;
+    Ok(Html(render_locations_page(&summaries, &[])))
+}
+
+fn render_locations_page(summaries: &[LocationSummary], errors: &[&str]) -> String {
+    let mut body = String::new();
+    body.push_str(r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Locations</h1>"#);
+
+    if !errors.is_empty() {
+        body.push_str(r#"<ul style="color: darkred;">"#);
+        for e in errors {
+            body.push_str(&format!("<li>{}</li>", html_escape(e)));
+        }
+        body.push_str("</ul>");
+    }
+
+    if summaries.is_empty() {
+        body.push_str(
+            "<p><em>No locations yet. Type one when adding items or editing a container.</em></p>\n",
+        );
+    } else {
+        body.push_str(
+            r#"<p style="color: gray; font-size: 0.85rem;">Rename a location, or merge it into another to fold duplicates like "bsmt" into "Basement".</p>
+    <table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#,
+        );
+        for s in summaries {
+            let id = s.location.id;
+            let mut merge_options = String::new();
+            for other in summaries.iter().filter(|o| o.location.id != id) {
+                merge_options.push_str(&format!(
+                    r#"<option value="{}">{}</option>"#,
+                    other.location.id,
+                    html_escape(&other.location.name)
+                ));
+            }
+            body.push_str(&format!(
+                r#"<tr>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;"><a href="/items?q={query}">{name}</a></td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; color: gray;">{items} item{items_plural}, {containers} container{containers_plural}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;">
+            <form method="post" action="/locations/{id}" style="margin: 0;">
+              <input name="name" type="text" value="{name}" aria-label="name" style="width: 8rem;">
+              <select name="merge_into" aria-label="merge into">
+                <option value="">Rename only</option>{merge_options}
+              </select>
+              <button type="submit">Save</button>
+            </form>
+          </td>
+        </tr>"#,
+                query = url_encode(&s.location.name),
+                name = html_escape(&s.location.name),
+                items = s.item_count,
+                items_plural = if s.item_count == 1 { "" } else { "s" },
+                containers = s.container_count,
+                containers_plural = if s.container_count == 1 { "" } else { "s" },
+            ));
+        }
+        body.push_str("</tbody></table>");
+    }
+
+    body.push_str(r#"<p style="margin-top: 1rem;"><a href="/items">Back to Trove</a></p>"#);
+    render_page("Locations", &body)
+}
+
+#[derive(Deserialize)]
+struct LocationForm {
+    name: String,
+    /// Id of the location to fold this one into; blank to just rename.
+    merge_into: Option<String>,
+}
+
+/// Renames a location, or merges it into another: everything set to it
+/// moves to the other and it's deleted.
+async fn handle_update_location(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(form): Form<LocationForm>,
+) -> Result<Response, AppError> {
+    let name = form.name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let merge_into = parse_container_select(form.merge_into.as_deref());
+
+    let error = state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let exists = tx
+                .query_row("SELECT 1 FROM locations WHERE id = ?1", params![id], |_| {
+                    Ok(())
+                })
+                .optional()?;
+            if exists.is_none() {
+                return Err(AppError::NotFound(format!("Location #{id}")));
+            }
+
+            if let Some(target) = merge_into {
+                if target == id {
+                    return Err(AppError::BadRequest(
+                        "A location can't be merged into itself.".to_string(),
+                    ));
+                }
+                let target_exists = tx
+                    .query_row(
+                        "SELECT 1 FROM locations WHERE id = ?1",
+                        params![target],
+                        |_| Ok(()),
+                    )
+                    .optional()?;
+                if target_exists.is_none() {
+                    return Err(AppError::NotFound(format!("Location #{target}")));
+                }
+                merge_location(&tx, id, target)?;
+                tx.commit()?;
+                return Ok(None);
+            }
+
+            if name.is_empty() {
+                drop(tx);
+                return Ok(Some("Name can't be empty."));
+            }
+            // Renames change what every snapshot would say, so log each owner.
+            let items = location_owners(&tx, "items", id)?;
+            let containers = location_owners(&tx, "containers", id)?;
+            let before = snapshot_all(&tx, &items, &containers)?;
+            match tx.execute(
+                "UPDATE locations SET name = ?1 WHERE id = ?2",
+                params![name, id],
+            ) {
+                Ok(_) => {}
+                Err(rusqlite::Error::SqliteFailure(e, _))
+                    if e.code == rusqlite::ErrorCode::ConstraintViolation =>
+                {
+                    drop(tx);
+                    return Ok(Some(
+                        "Another location already has that name; merge into it instead.",
+                    ));
+                }
+                Err(e) => return Err(e.into()),
+            }
+            log_location_change(&tx, before, EventAction::Update)?;
+            tx.commit()?;
+            Ok(None)
+        })
+        .await?;
+
+    match error {
+        None => Ok(Redirect::to("/locations").into_response()),
+        Some(error) => {
+            let summaries = state
+                .with_db(|conn| Ok(load_location_summaries(conn)?))
+                .await?;
+            let html = render_locations_page(&summaries, &[error]);
+            Ok((StatusCode::UNPROCESSABLE_ENTITY, Html(html)).into_response())
+        }
+    }
+}
+
+/// Ids of the items or containers set directly to a location.
+fn location_owners(tx: &Transaction, table: &str, location_id: i64) -> rusqlite::Result<Vec<i64>> {
+    let mut stmt = tx.prepare(&format!("SELECT id FROM {table} WHERE location_id = ?1"))?;
+    let rows = stmt.query_map(params![location_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+type OwnerSnapshot = (EntityKind, i64, Option<String>);
+
+fn snapshot_all(
+    tx: &Transaction,
+    items: &[i64],
+    containers: &[i64],
+) -> rusqlite::Result<Vec<OwnerSnapshot>> {
+    let mut snapshots = Vec::with_capacity(items.len() + containers.len());
+    for &id in items {
+        snapshots.push((EntityKind::Item, id, item_snapshot(tx, id)?));
+    }
+    for &id in containers {
+        snapshots.push((EntityKind::Container, id, container_snapshot(tx, id)?));
+    }
+    Ok(snapshots)
+}
+
+/// Records an event for each owner whose snapshot changed since `before`.
+fn log_location_change(
+    tx: &Transaction,
+    before: Vec<OwnerSnapshot>,
+    action: EventAction,
+) -> rusqlite::Result<()> {
+    for (kind, id, before) in before {
+        let after = entity_snapshot(tx, kind, id)?;
+        if after != before {
+            record_event(tx, kind, id, action, before, after, EventSource::WebForm)?;
+        }
+    }
+    Ok(())
+}
+
+fn merge_location(tx: &Transaction, from: i64, into: i64) -> rusqlite::Result<()> {
+    let items = location_owners(tx, "items", from)?;
+    let containers = location_owners(tx, "containers", from)?;
+    let before = snapshot_all(tx, &items, &containers)?;
+    tx.execute(
+        "UPDATE items SET location_id = ?1 WHERE location_id = ?2",
+        params![into, from],
+    )?;
+    tx.execute(
+        "UPDATE containers SET location_id = ?1 WHERE location_id = ?2",
+        params![into, from],
+    )?;
+    tx.execute("DELETE FROM locations WHERE id = ?1", params![from])?;
+    log_location_change(tx, before, EventAction::Move)
+}
+
+/// Suggestions offered for `containers.kind`; any other text is accepted too.
+const CONTAINER_KINDS: &[&str] = &["bin", "drawer", "shelf", "box", "cabinet", "bag", "case"];
+
+#[derive(Debug)]
+struct ContainerSummary {
+    container: Container,
+    item_count: i64,
+    /// Where it is, whether its own location or an enclosing container's.
+    location: Option<String>,
+}
+
+async fn show_containers(State(state): State<AppState>) -> Result<Html<String>, AppError> {
+    let mut summaries = state
+        .with_db(|conn| Ok(load_container_summaries(conn)?))
+        .await?;
+    let containers: Vec<Container> = summaries.iter().map(|s| s.container.clone()).collect();
+    let paths = container_paths(&containers);
+    // Drawers are listed on their cabinet's page.
+    summaries.retain(|s| s.container.cell.is_none());
+    summaries.sort_by_cached_key(|s| {
+        paths
+            .get(&s.container.id)
+            .map(|p| path_sort_key(p))
+            .unwrap_or_default()
+    });
+
+    let mut body = String::new();
+    body.push_str(r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Containers</h1>"#);
+
+    if summaries.is_empty() {
+        body.push_str("<p><em>No containers yet.</em></p>\n");
+    } else {
+        body.push_str(
+            r#"<table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#,
+        );
+        for s in &summaries {
+            let mut kind = s.container.kind.clone().unwrap_or_default();
+            if let Some((rows, cols)) = s.container.grid {
+                kind.push_str(&format!(" {rows}×{cols}"));
+            }
+            body.push_str(&format!(
+                r#"<tr>
+          <td style="padding: 2px 4px 2px {indent}rem; border-top: 1px solid #eee;"><a href="/containers/{id}">{name}</a></td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; color: gray;">{kind}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; color: gray;">{location}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right;">{count} item{plural}</td>
+        </tr>"#,
+                indent = 0.25
+                    + paths
+                        .get(&s.container.id)
+                        .map_or(0, |p| p.len().saturating_sub(1)) as f64,
+                id = s.container.id,
+                name = html_escape(&s.container.name),
+                kind = html_escape(&kind),
+                location = html_escape(s.location.as_deref().unwrap_or("")),
+                count = s.item_count,
+                plural = if s.item_count == 1 { "" } else { "s" },
+            ));
+        }
+        body.push_str("</tbody></table>");
+    }
+
+    body.push_str(r#"<p style="margin-top: 1rem;"><a href="/labels/blank">Print blank bin labels</a> · <a href="/items">Back to Trove</a></p>"#);
+
+    Ok(Html(render_page("Containers", &body)))
+}
+
+async fn show_container(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Html<String>, AppError> {
+    let page = state
+        .with_db(move |conn| load_container_page(conn, id))
+        .await?;
+
+    Ok(Html(render_container_page(&page, &[])))
+}
+
+/// Everything shown on a container's detail page.
+struct ContainerPage {
+    container: Container,
+    items: Vec<Item>,
+    /// Every other container, as targets for moves and deletes.
+    others: Vec<Container>,
+    /// Path of every container, for breadcrumbs and dropdown labels.
+    paths: HashMap<i64, Vec<(i64, String)>>,
+    /// Containers directly inside this one, other than its drawers.
+    children: Vec<Container>,
+    /// Drawers of a grid cabinet with what's in each.
+    cells: Vec<(Container, Vec<Item>)>,
+    locations: Vec<Location>,
+    /// The container's own location, for the form.
+    location: Option<String>,
+    /// Where it is, whether its own location or an enclosing container's.
+    placed: Option<String>,
+    photos: Vec<Photo>,
+    /// First-photo thumbnail per item id, for items that have one.
+    item_thumbs: HashMap<i64, String>,
+    events: Vec<Event>,
+}
+
+fn load_container_page(conn: &Connection, id: i64) -> Result<ContainerPage, AppError> {
+    let container = load_container(conn, id)?;
+    let items = load_container_items(conn, id)?;
+    let all = load_containers(conn)?;
+    let paths = container_paths(&all);
+    let others: Vec<Container> = all.into_iter().filter(|c| c.id != id).collect();
+    let (cells, children): (Vec<Container>, Vec<Container>) = others
+        .iter()
+        .filter(|c| c.parent_id == Some(id))
+        .cloned()
+        .partition(|c| c.cell.is_some());
+    let cells = cells
+        .into_iter()
+        .map(|c| {
+            let items = load_container_items(conn, c.id)?;
+            Ok((c, items))
+        })
+        .collect::<rusqlite::Result<_>>()?;
+    let locations = load_locations(conn)?;
+    let location = locations
+        .iter()
+        .find(|l| Some(l.id) == container.location_id)
+        .map(|l| l.name.clone());
+    let placed = container_location(conn, Some(id))?.map(|l| l.name);
+    let photos = load_photos(conn, EntityKind::Container, id)?;
+    let item_thumbs = load_item_thumbs(conn, id)?;
+    let events = load_container_events(conn, id)?;
+
+    Ok(ContainerPage {
+        container,
+        items,
+        others,
+        paths,
+        children,
+        cells,
+        locations,
+        location,
+        placed,
+        photos,
+        item_thumbs,
+        events,
+    })
+}
+
+fn render_container_page(page: &ContainerPage, errors: &[&str]) -> String {
+    let ContainerPage {
+        container,
+        items,
+        others,
+        paths,
+        children,
+        cells,
+        locations,
+        location,
+        placed,
+        photos,
+        item_thumbs,
+        events,
+    } = page;
+    let id = container.id;
+    let mut body = String::new();
+
+    let path = paths.get(&id).map(Vec::as_slice).unwrap_or_default();
+    if path.len() > 1 {
+        body.push_str(&format!(
+            r#"<p style="font-size: 0.85rem; margin-bottom: 0;">{}</p>"#,
+            render_breadcrumbs(&path[..path.len() - 1])
+        ));
+    }
+    body.push_str(&format!(
+        r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.25rem;">{name}</h1>
+    <p style="color: gray; margin-top: 0;">{kind}{placed}</p>"#,
+        name = html_escape(&container.name),
+        kind = html_escape(container.kind.as_deref().unwrap_or("no kind set")),
+        placed = placed
+            .as_deref()
+            .map(|l| format!(" · in {}", html_escape(l)))
+            .unwrap_or_default(),
+    ));
+
+    if !children.is_empty() {
+        let links: Vec<String> = children
+            .iter()
+            .map(|c| {
+                format!(
+                    r#"<a href="/containers/{}">{}</a>"#,
+                    c.id,
+                    html_escape(&c.name)
+                )
+            })
+            .collect();
+        body.push_str(&format!("<p>Inside: {}</p>", links.join(", ")));
+    }
+
+    if let Some((rows, cols)) = container.grid {
+        body.push_str(&render_grid(rows, cols, cells));
+    }
+
+    if !errors.is_empty() {
+        body.push_str(r#"<ul style="color: darkred;">"#);
+        for e in errors {
+            body.push_str(&format!("<li>{}</li>", html_escape(e)));
+        }
+        body.push_str("</ul>");
+    }
+
+    if items.is_empty() {
+        body.push_str("<p><em>Nothing in here yet.</em></p>\n");
+    } else {
+        body.push_str(&format!(
+            r#"<form method="post" action="/containers/{id}/move">
+    <table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#
+        ));
+        for item in items {
+            let mut line = item_thumbs
+                .get(&item.id)
+                .map(|thumb| render_thumb(thumb, "2rem"))
+                .unwrap_or_default();
+            line.push_str(&format_amount(
+                item.quantity,
+                &item.unit,
+                &html_escape(&item.name),
+            ));
+            // Only items kept somewhere other than the container say where.
+            if let (Some(loc), Some(_)) = (&item.location, item.location_id) {
+                line.push_str(&format!(" — {}", html_escape(loc)));
+            }
+            body.push_str(&format!(
+                r#"<tr>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; width: 1.5rem;"><input type="checkbox" name="item_id" value="{item_id}" aria-label="select"></td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;">{line}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right;"><a href="/items/{item_id}/edit">edit</a></td>
+        </tr>"#,
+                item_id = item.id,
+            ));
+        }
+        body.push_str(
+            r#"</tbody></table>
+      <h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Move checked items</h2>
+      <label for="move_container_select">To container:</label><br>
+      <select id="move_container_select" name="container_select" style="width: 100%;">
+        <option value="">-- Choose --</option>"#,
+        );
+        for c in others {
+            body.push_str(&format!(
+                r#"<option value="{}">{}</option>"#,
+                c.id,
+                html_escape(&paths.get(&c.id).map(|p| path_label(p)).unwrap_or_default())
+            ));
+        }
+        body.push_str(
+            r#"</select><br><br>
+      <label for="move_container_new">Or a new container:</label><br>
+      <input id="move_container_new" name="container_new" type="text" style="width: 100%;" /><br><br>
+      <label for="move_location">New location (optional, leave blank to keep):</label><br>
+      <input id="move_location" name="location" type="text" list="locations" style="width: 100%;" /><br><br>
+      <button type="submit">Move</button>
+    </form>"#,
+        );
+    }
+
+    body.push_str(&format!(
+        r#"<form method="post" action="/labels/print" style="margin-top: 1rem;">
+      <input type="hidden" name="container_id" value="{id}">
+      <button type="submit">Print label</button>
+    </form>"#
+    ));
+    if !cells.is_empty() {
+        body.push_str(r#"<form method="post" action="/labels/print" style="margin-top: 0.5rem;">"#);
+        for (cell, _) in cells {
+            body.push_str(&format!(
+                r#"<input type="hidden" name="container_id" value="{}">"#,
+                cell.id
+            ));
+        }
+        body.push_str(&format!(
+            r#"<button type="submit">Print all {} drawer labels</button></form>"#,
+            cells.len()
+        ));
+    }
+
+    body.push_str(&render_photos(photos, &format!("/containers/{id}/photos")));
+
+    let (rows, cols) = container.grid.unzip();
+    body.push_str(&format!(
+        r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Drawer grid</h2>
+    <form method="post" action="/containers/{id}/grid">
+      <label for="grid_rows">Rows (A–Z):</label>
+      <input id="grid_rows" name="rows" type="number" min="1" max="{MAX_GRID_ROWS}" value="{rows}" style="width: 4rem;" />
+      <label for="grid_cols">Columns:</label>
+      <input id="grid_cols" name="cols" type="number" min="1" max="{MAX_GRID_COLS}" value="{cols}" style="width: 4rem;" />
+      <button type="submit">{action}</button>
+    </form>"#,
+        rows = rows.map(|r| r.to_string()).unwrap_or_default(),
+        cols = cols.map(|c| c.to_string()).unwrap_or_default(),
+        action = if container.grid.is_some() {
+            "Resize grid"
+        } else {
+            "Make it a grid cabinet"
+        },
+    ));
+
+    body.push_str(&format!(
+        r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Rename, set kind or move</h2>
+    <form method="post" action="/containers/{id}">
+      <label for="name">Name:</label><br>
+      <input id="name" name="name" type="text" value="{name}" style="width: 100%;" /><br><br>
+      <label for="kind">Kind (bin, drawer, shelf…):</label><br>
+      <input id="kind" name="kind" type="text" list="container_kinds" value="{kind}" style="width: 100%;" />
+      <datalist id="container_kinds">"#,
+        name = html_escape(&container.name),
+        kind = html_escape(container.kind.as_deref().unwrap_or("")),
+    ));
+    for k in CONTAINER_KINDS {
+        body.push_str(&format!(r#"<option value="{k}">"#));
+    }
+    body.push_str(&format!(
+        r#"</datalist><br><br>
+      <label for="location">Location (blank to go with what it's inside):</label><br>
+      <input id="location" name="location" type="text" list="locations" value="{location}" style="width: 100%;" /><br><br>
+      {locations}
+      <label for="parent">Inside:</label><br>
+      <select id="parent" name="parent" style="width: 100%;">
+        <option value="">Nothing (top level)</option>"#,
+        location = html_escape(location.as_deref().unwrap_or("")),
+        locations = render_location_datalist(locations),
+    ));
+    for c in others {
+        // Its own descendants can't hold it.
+        let below = paths
+            .get(&c.id)
+            .is_some_and(|p| p.iter().any(|(cid, _)| *cid == id));
+        if below {
+            continue;
+        }
+        body.push_str(&format!(
+            r#"<option value="{}"{}>{}</option>"#,
+            c.id,
+            if container.parent_id == Some(c.id) {
+                " selected"
+            } else {
+                ""
+            },
+            html_escape(&paths.get(&c.id).map(|p| path_label(p)).unwrap_or_default())
+        ));
+    }
+    body.push_str(
+        r#"</select><br><br>
+      <button type="submit">Save</button>
+    </form>"#,
+    );
+
+    body.push_str(&format!(
+        r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Delete container</h2>
+    <form method="post" action="/containers/{id}/delete">
+      <label for="move_to">Move its items to:</label><br>
+      <select id="move_to" name="move_to" style="width: 100%;">
+        <option value="">Loose (no container)</option>"#
+    ));
+    for c in others {
+        body.push_str(&format!(
+            r#"<option value="{}">{}</option>"#,
+            c.id,
+            html_escape(&paths.get(&c.id).map(|p| path_label(p)).unwrap_or_default())
+        ));
+    }
+    body.push_str(
+        r#"</select><br><br>
+      <button type="submit">Delete</button>
+    </form>
+    "#,
+    );
+
+    body.push_str(&render_timeline(events));
+    body.push_str(
+        r#"<p style="margin-top: 1rem;"><a href="/containers">All containers</a> · <a href="/items">Back to Trove</a></p>"#,
+    );
+
+    render_page(&container.name, &body)
+}
+
+const MAX_GRID_ROWS: i64 = 26;
+const MAX_GRID_COLS: i64 = 50;
+
+/// "A1" for the top-left drawer; `row` and `col` count from zero.
+fn cell_label(row: i64, col: i64) -> String {
+    format!("{}{}", char::from(b'A' + row as u8), col + 1)
+}
+
+/// Zero-based row and column of a drawer address.
+fn parse_cell(label: &str) -> Option<(i64, i64)> {
+    let mut chars = label.chars();
+    let letter = chars.next().filter(char::is_ascii_uppercase)?;
+    let digits = chars.as_str();
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let col: i64 = digits.parse().ok().filter(|&c| c >= 1)?;
+    Some((i64::from(letter as u8 - b'A'), col - 1))
+}
+
+/// The cabinet's drawers as a table, each with what's in it.
+fn render_grid(rows: i64, cols: i64, cells: &[(Container, Vec<Item>)]) -> String {
+    let by_label: HashMap<&str, &(Container, Vec<Item>)> = cells
+        .iter()
+        .filter_map(|entry| entry.0.cell.as_deref().map(|l| (l, entry)))
+        .collect();
+
+    let mut html = String::from(
+        r#"<table style="width: 100%; border-collapse: collapse; table-layout: fixed; font-size: 0.8rem; margin-bottom: 1rem;"><tbody>"#,
+    );
+    for row in 0..rows {
+        html.push_str("<tr>");
+        for col in 0..cols {
+            let label = cell_label(row, col);
+            let contents = match by_label.get(label.as_str()) {
+                Some((cell, items)) => {
+                    let mut lines = vec![format!(
+                        r#"<a href="/containers/{}"><strong>{label}</strong></a>"#,
+                        cell.id
+                    )];
+                    if items.is_empty() {
+                        lines.push(r#"<span style="color: gray;">empty</span>"#.to_string());
+                    }
+                    for item in items {
+                        lines.push(format!(
+                            r#"<a href="/items/{}/edit" style="color: inherit;">{}</a>"#,
+                            item.id,
+                            format_amount(item.quantity, &item.unit, &html_escape(&item.name))
+                        ));
+                    }
+                    lines.join("<br>")
+                }
+                None => format!(r#"<span style="color: gray;">{label}</span>"#),
+            };
+            html.push_str(&format!(
+                r#"<td style="padding: 2px 4px; border: 1px solid #ccc; vertical-align: top;">{contents}</td>"#
+            ));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</tbody></table>");
+    html
+}
+
+#[derive(Deserialize)]
+struct GridForm {
+    rows: String,
+    cols: String,
+}
+
+/// Makes a container a grid cabinet, or resizes one: creates a drawer
+/// container for every new cell and removes cells that fall outside, which
+/// must be empty.
+async fn handle_set_grid(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(form): Form<GridForm>,
+) -> Result<Response, AppError> {
+    let rows = form.rows.trim().parse::<i64>().ok();
+    let cols = form.cols.trim().parse::<i64>().ok();
+    let media_dir = PathBuf::from(&state.config.media_dir);
+
+    let outcome = state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let container = load_container(&tx, id)?;
+
+            let mut errors: Vec<String> = Vec::new();
+            let mut removed_photos = Vec::new();
+            let size = match (rows, cols) {
+                (Some(r), Some(c))
+                    if (1..=MAX_GRID_ROWS).contains(&r) && (1..=MAX_GRID_COLS).contains(&c) =>
+                {
+                    Some((r, c))
+                }
+                _ => {
+                    errors.push(format!(
+                        "A grid needs 1 to {MAX_GRID_ROWS} rows and 1 to {MAX_GRID_COLS} columns."
+                    ));
+                    None
+                }
+            };
+
+            if let Some((rows, cols)) = size {
+                let existing: Vec<Container> = load_containers(&tx)?
+                    .into_iter()
+                    .filter(|c| c.parent_id == Some(id) && c.cell.is_some())
+                    .collect();
+
+                for cell in &existing {
+                    let inside = parse_cell(cell.cell.as_deref().unwrap_or(""))
+                        .is_some_and(|(r, c)| r < rows && c < cols);
+                    if inside {
+                        continue;
+                    }
+                    // Trashed items count: deleting would orphan them.
+                    let in_use: bool = tx.query_row(
+                        "SELECT EXISTS (SELECT 1 FROM items WHERE container_id = ?1)
+                             OR EXISTS (SELECT 1 FROM containers WHERE parent_id = ?1)",
+                        params![cell.id],
+                        |row| row.get(0),
+                    )?;
+                    if in_use {
+                        errors.push(format!(
+                            "Drawer {} isn't empty, so the grid can't shrink past it.",
+                            cell.cell.as_deref().unwrap_or("")
+                        ));
+                        continue;
+                    }
+                    let before = container_snapshot(&tx, cell.id)?;
+                    removed_photos.extend(load_photos(&tx, EntityKind::Container, cell.id)?);
+                    tx.execute("DELETE FROM containers WHERE id = ?1", params![cell.id])?;
+                    record_event(
+                        &tx,
+                        EntityKind::Container,
+                        cell.id,
+                        EventAction::Delete,
+                        before,
+                        None,
+                        EventSource::WebForm,
+                    )?;
+                }
+
+                let taken: HashSet<String> =
+                    existing.iter().filter_map(|c| c.cell.clone()).collect();
+                for row in 0..rows {
+                    for col in 0..cols {
+                        let label = cell_label(row, col);
+                        if taken.contains(&label) {
+                            continue;
+                        }
+                        let name = format!("{} {label}", container.name);
+                        let inserted = tx.execute(
+                            "INSERT OR IGNORE INTO containers (name, kind, parent_id, cell)
+                             VALUES (?1, 'drawer', ?2, ?3)",
+                            params![name, id, label],
+                        )?;
+                        if inserted == 0 {
+                            errors.push(format!("Another container is already named {name}."));
+                            continue;
+                        }
+                        let cell_id = tx.last_insert_rowid();
+                        let after = container_snapshot(&tx, cell_id)?;
+                        record_event(
+                            &tx,
+                            EntityKind::Container,
+                            cell_id,
+                            EventAction::Create,
+                            None,
+                            after,
+                            EventSource::WebForm,
+                        )?;
+                    }
+                }
+
+                let before = container_snapshot(&tx, id)?;
+                tx.execute(
+                    // Keep a kind the user chose, like "cabinet".
+                    "UPDATE containers
+                     SET kind = CASE WHEN trim(coalesce(kind, '')) = '' THEN 'grid' ELSE kind END,
+                         grid_rows = ?1, grid_cols = ?2
+                     WHERE id = ?3",
+                    params![rows, cols, id],
+                )?;
+                let after = container_snapshot(&tx, id)?;
+                if after != before {
+                    record_event(
+                        &tx,
+                        EntityKind::Container,
+                        id,
+                        EventAction::Update,
+                        before,
+                        after,
+                        EventSource::WebForm,
+                    )?;
+                }
+            }
+
+            if errors.is_empty() {
+                tx.commit()?;
+                remove_photo_files(&media_dir, &removed_photos);
+                return Ok(None);
+            }
+            drop(tx);
+            let page = load_container_page(conn, id)?;
+            let errors: Vec<&str> = errors.iter().map(String::as_str).collect();
+            Ok(Some(render_container_page(&page, &errors)))
+        })
+        .await?;
+
+    match outcome {
+        None => Ok(Redirect::to(&format!("/containers/{id}")).into_response()),
+        Some(html) => Ok((StatusCode::UNPROCESSABLE_ENTITY, Html(html)).into_response()),
+    }
+}
+
+#[derive(Deserialize)]
+struct ContainerForm {
+    name: String,
+    kind: Option<String>,
+    /// Id of the enclosing container; blank for top level.
+    parent: Option<String>,
+    location: Option<String>,
+}
+
+async fn handle_update_container(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(form): Form<ContainerForm>,
+) -> Result<Response, AppError> {
+    let name = form.name.trim().to_string();
+    let kind = normalize_optional(form.kind);
+    let parent_id = parse_container_select(form.parent.as_deref());
+    let location = normalize_optional(form.location);
+
+    let outcome = state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let old = load_container(&tx, id)?;
+            if let Some(parent) = parent_id {
+                load_container(&tx, parent)?;
+            }
+            let before = container_snapshot(&tx, id)?;
+            // The new parent's path contains this container exactly when the
+            // parent is this container or sits somewhere inside it.
+            let paths = container_paths(&load_containers(&tx)?);
+            let loops = parent_id.is_some_and(|parent| {
+                paths
+                    .get(&parent)
+                    .is_some_and(|p| p.iter().any(|(cid, _)| *cid == id))
+            });
+
+            // Rolled back with everything else if the form is rejected.
+            let location_id = resolve_location(&tx, location.as_deref())?;
+
+            let error = if name.is_empty() {
+                Some("Name can't be empty.")
+            } else if loops {
+                Some("A container can't go inside itself or anything inside it.")
+            } else {
+                match tx.execute(
+                    // A drawer taken out of its cabinet is an ordinary container.
+                    "UPDATE containers
+                     SET name = ?1, kind = ?2, parent_id = ?3, location_id = ?4,
+                         cell = CASE WHEN parent_id IS ?3 THEN cell END
+                     WHERE id = ?5",
+                    params![name, kind, parent_id, location_id, id],
+                ) {
+                    Ok(_) if old.name != name => rename_drawers(&tx, id, &name)?,
+                    Ok(_) => None,
+                    Err(rusqlite::Error::SqliteFailure(e, _))
+                        if e.code == rusqlite::ErrorCode::ConstraintViolation =>
+                    {
+                        Some("Another container already has that name.")
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            };
+
+            match error {
+                None => {
+                    let after = container_snapshot(&tx, id)?;
+                    let moved_only = old.name == name
+                        && old.kind == kind
+                        && (old.parent_id != parent_id || old.location_id != location_id);
+                    if after != before {
+                        record_event(
+                            &tx,
+                            EntityKind::Container,
+                            id,
+                            if moved_only {
+                                EventAction::Move
+                            } else {
+                                EventAction::Update
+                            },
+                            before,
+                            after,
+                            EventSource::WebForm,
+                        )?;
+                    }
+                    tx.commit()?;
+                    Ok(None)
+                }
+                Some(error) => {
+                    drop(tx);
+                    let mut page = load_container_page(conn, id)?;
+                    page.container.kind = kind;
+                    page.container.parent_id = parent_id;
+                    page.location = location;
+                    Ok(Some(render_container_page(&page, &[error])))
+                }
+            }
+        })
+        .await?;
+
+    match outcome {
+        None => Ok(Redirect::to(&format!("/containers/{id}")).into_response()),
+        Some(html) => Ok((StatusCode::UNPROCESSABLE_ENTITY, Html(html)).into_response()),
+    }
+}
+
+/// Renames a cabinet's drawers after it, so "Parts A1" follows a rename of
+/// "Parts". Returns an error message if a new name is taken.
+fn rename_drawers(tx: &Transaction, id: i64, name: &str) -> rusqlite::Result<Option<&'static str>> {
+    let drawers: Vec<i64> = tx
+        .prepare("SELECT id FROM containers WHERE parent_id = ?1 AND cell IS NOT NULL")?
+        .query_map(params![id], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for drawer_id in drawers {
+        let before = container_snapshot(tx, drawer_id)?;
+        match tx.execute(
+            "UPDATE containers SET name = ?1 || ' ' || cell WHERE id = ?2",
+            params![name, drawer_id],
+        ) {
+            Ok(_) => {}
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                return Ok(Some(
+                    "Another container already has one of its drawers' new names.",
+                ));
+            }
+            Err(e) => return Err(e),
+        }
+        let after = container_snapshot(tx, drawer_id)?;
+        record_event(
+            tx,
+            EntityKind::Container,
+            drawer_id,
+            EventAction::Update,
+            before,
+            after,
+            EventSource::WebForm,
+        )?;
+    }
+    Ok(None)
+}
+
+#[derive(Deserialize)]
+struct DeleteContainerForm {
+    move_to: Option<String>,
+}
+
+async fn handle_delete_container(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(form): Form<DeleteContainerForm>,
+) -> Result<Redirect, AppError> {
+    let move_to = parse_container_select(form.move_to.as_deref());
+    let media_dir = PathBuf::from(&state.config.media_dir);
+    if move_to == Some(id) {
+        return Err(AppError::BadRequest(
+            "Can't move items into the container being deleted.".to_string(),
+        ));
+    }
+
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let doomed = load_container(&tx, id)?;
+            if let Some(target) = move_to {
+                load_container(&tx, target)?;
+            }
+
+            // Containers inside it move up a level rather than to the top;
+            // a grid's drawers become ordinary containers.
+            let child_ids: Vec<i64> = tx
+                .prepare("SELECT id FROM containers WHERE parent_id = ?1")?
+                .query_map(params![id], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            for child_id in child_ids {
+                let before = container_snapshot(&tx, child_id)?;
+                tx.execute(
+                    "UPDATE containers SET parent_id = ?1, cell = NULL WHERE id = ?2",
+                    params![doomed.parent_id, child_id],
+                )?;
+                let after = container_snapshot(&tx, child_id)?;
+                record_event(
+                    &tx,
+                    EntityKind::Container,
+                    child_id,
+                    EventAction::Move,
+                    before,
+                    after,
+                    EventSource::WebForm,
+                )?;
+            }
+
+            // Trashed items move too, so the foreign key never dangles.
+            let item_ids: Vec<i64> = tx
+                .prepare("SELECT id FROM items WHERE container_id = ?1")?
+                .query_map(params![id], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            for item_id in item_ids {
+                let before = item_snapshot(&tx, item_id)?;
+                tx.execute(
+                    "UPDATE items SET container_id = ?1 WHERE id = ?2",
+                    params![move_to, item_id],
+                )?;
+                let after = item_snapshot(&tx, item_id)?;
+                record_event(
+                    &tx,
+                    EntityKind::Item,
+                    item_id,
+                    EventAction::Move,
+                    before,
+                    after,
+                    EventSource::WebForm,
+                )?;
+            }
+
+            let before = container_snapshot(&tx, id)?;
+            let photos = load_photos(&tx, EntityKind::Container, id)?;
+            tx.execute("DELETE FROM containers WHERE id = ?1", params![id])?;
+            record_event(
+                &tx,
+                EntityKind::Container,
+                id,
+                EventAction::Delete,
+                before,
+                None,
+                EventSource::WebForm,
+            )?;
+
+            tx.commit()?;
+            remove_photo_files(&media_dir, &photos);
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to("/containers"))
+}
+
+async fn handle_move_items(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Form(fields): Form<Vec<(String, String)>>,
+) -> Result<Html<String>, AppError> {
+    let item_ids: Vec<i64> = form_values(&fields, "item_id")
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    let container_select = parse_container_select(form_values(&fields, "container_select").next());
+    let container_new = form_values(&fields, "container_new")
+        .next()
+        .map(str::to_string);
+    let location = form_values(&fields, "location").next().map(str::to_string);
+
+    if item_ids.is_empty() {
+        return Err(AppError::BadRequest(
+            "No items were checked, so nothing was moved.".to_string(),
+        ));
+    }
+
+    let (from, to, moved) = state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let from = load_container(&tx, id)?;
+
+            let (to_id, _) = choose_container(&tx, container_select, container_new, None)?;
+            let to_id = to_id.ok_or_else(|| {
+                AppError::BadRequest("Pick a container to move the items to.".to_string())
+            })?;
+            if to_id == id {
+                return Err(AppError::BadRequest(
+                    "The items are already in that container.".to_string(),
+                ));
+            }
+            let to = load_container(&tx, to_id)?;
+            let location_id = resolve_location(&tx, location.as_deref())?;
+
+            let mut moved = 0;
+            for &item_id in &item_ids {
+                let before = item_snapshot(&tx, item_id)?;
+                let changed = tx.execute(
+                    "UPDATE items
+                     SET container_id = ?1, location_id = COALESCE(?2, location_id)
+                     WHERE id = ?3 AND container_id = ?4 AND deleted_at IS NULL",
+                    params![to_id, location_id, item_id, id],
+                )?;
+                if changed == 1 {
+                    let after = item_snapshot(&tx, item_id)?;
+                    record_event(
+                        &tx,
+                        EntityKind::Item,
+                        item_id,
+                        EventAction::Move,
+                        before,
+                        after,
+                        EventSource::WebForm,
+                    )?;
+                    moved += 1;
+                }
+            }
+
+            tx.commit()?;
+            Ok((from, to, moved))
+        })
+        .await?;
+
+    let body = format!(
+        r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Moved</h1>
+    <p>Moved {moved} item{plural} from <a href="/containers/{from_id}">{from_name}</a> to <a href="/containers/{to_id}">{to_name}</a>.</p>
+    <form method="post" action="/labels/print">
+      <input type="hidden" name="container_id" value="{from_id}">
+      <input type="hidden" name="container_id" value="{to_id}">
+      <button type="submit">Reprint both labels</button>
+    </form>
+    <p style="margin-top: 1rem;"><a href="/containers/{from_id}">Back to {from_name}</a> · <a href="/items">Back to Trove</a></p>"#,
+        plural = if moved == 1 { "" } else { "s" },
+        from_id = from.id,
+        from_name = html_escape(&from.name),
+        to_id = to.id,
+        to_name = html_escape(&to.name),
+    );
+
+    Ok(Html(render_page("Moved", &body)))
+}
+
+/// Prints the current contents label for every `container_id` in the form,
+/// all in one print job.
+async fn handle_print_labels(
+    State(state): State<AppState>,
+    Form(fields): Form<Vec<(String, String)>>,
+) -> Result<Html<String>, AppError> {
+    let ids: Vec<i64> = form_values(&fields, "container_id")
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    if ids.is_empty() {
+        return Err(AppError::BadRequest("No container was chosen.".to_string()));
+    }
+
+    let labels = state
+        .with_db(move |conn| {
+            let paths = load_container_paths(conn)?;
+            let mut labels = Vec::new();
+            for id in ids {
+                let container = load_container(conn, id)?;
+                let items = load_container_items(conn, id)?;
+                let header = paths.get(&id).map(|p| path_label(p)).unwrap_or_default();
+                labels.push((container, items, header));
+            }
+            Ok(labels)
+        })
+        .await?;
+
+    let zpl: String = labels
+        .iter()
+        .map(|(container, items, header)| {
+            let page_url = state.config.container_url(container.id);
+            zebra_label(items, Some(header), page_url.as_deref())
+        })
+        .collect();
+    let outcome = match state.print_zpl(zpl).await {
+        Ok(()) => "sent to printer".to_string(),
+        Err(e) => {
+            eprintln!("Failed to print zebra labels: {e}");
+            format!("did not print: {e}")
+        }
+    };
+
+    let mut body =
+        String::from(r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Labels</h1><ul>"#);
+    for (container, _, _) in &labels {
+        body.push_str(&format!(
+            r#"<li><a href="/containers/{}">{}</a> — {}</li>"#,
+            container.id,
+            html_escape(&container.name),
+            html_escape(&outcome)
+        ));
+    }
+    body.push_str(r#"</ul><p style="margin-top: 1rem;"><a href="/containers">All containers</a> · <a href="/items">Back to Trove</a></p>"#);
+
+    Ok(Html(render_page("Labels", &body)))
+}
+
+/// Most blank labels one request will print.
+const MAX_BLANK_LABELS: i64 = 100;
+
+async fn show_blank_labels(State(state): State<AppState>) -> Result<Html<String>, AppError> {
+    let scheme = state.config.name_scheme().map(str::to_string);
+    let next_name = match scheme {
+        Some(scheme) => Some(
+            state
+                .with_db(move |conn| Ok(peek_container_name(conn, &scheme)?))
+                .await?,
+        ),
+        None => None,
+    };
+
+    let mut body = String::from(
+        r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Blank bin labels</h1>"#,
+    );
+    match next_name {
+        Some(next_name) => body.push_str(&format!(
+            r#"<p>Creates empty bins numbered from <strong>{next}</strong> on and prints their labels in one job, ready to stick on before anything goes in.</p>
+    <form method="post" action="/labels/blank">
+      <label for="count">How many:</label>
+      <input id="count" name="count" type="number" min="1" max="{MAX_BLANK_LABELS}" value="10" style="width: 5rem;" />
+      <button type="submit">Create and print</button>
+    </form>"#,
+            next = html_escape(&next_name),
+        )),
+        None => body.push_str(
+            r#"<p><em>Set <code>container_name_scheme</code> (for example <code>"BIN-{:04}"</code>) in the config file to number bins automatically.</em></p>"#,
+        ),
+    }
+    body.push_str(r#"<p style="margin-top: 1rem;"><a href="/containers">All containers</a> · <a href="/items">Back to Trove</a></p>"#);
+
+    Ok(Html(render_page("Blank bin labels", &body)))
+}
+
+/// Removes bins reserved for blank labels that never printed, with their
+/// create events, and hands the numbers back unless a later run has taken
+/// some since.
+fn release_blank_bins(
+    conn: &mut Connection,
+    scheme: &str,
+    ids: &[i64],
+    previous_last: Option<i64>,
+    reserved_last: i64,
+) -> Result<(), AppError> {
+    let tx = conn.transaction()?;
+    for id in ids {
+        tx.execute(
+            "DELETE FROM events WHERE entity_type = ?1 AND entity_id = ?2",
+            params![EntityKind::Container.as_str(), id],
+        )?;
+        tx.execute("DELETE FROM containers WHERE id = ?1", params![id])?;
+    }
+    tx.execute(
+        "UPDATE name_sequences SET last = ?1 WHERE scheme = ?2 AND last = ?3",
+        params![previous_last.unwrap_or(0), scheme, reserved_last],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct BlankLabelsForm {
+    count: String,
+}
+
+/// Creates `count` empty bins under the naming scheme and prints all their
+/// labels in a single CUPS job.
+async fn handle_print_blank_labels(
+    State(state): State<AppState>,
+    Form(form): Form<BlankLabelsForm>,
+) -> Result<Html<String>, AppError> {
+    let scheme = state
+        .config
+        .name_scheme()
+        .ok_or_else(|| AppError::BadRequest("No container_name_scheme is configured.".to_string()))?
+        .to_string();
+    let count = form
+        .count
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .filter(|n| (1..=MAX_BLANK_LABELS).contains(n))
+        .ok_or_else(|| {
+            AppError::BadRequest(format!("Print 1 to {MAX_BLANK_LABELS} labels at a time."))
+        })?;
+
+    // The numbers are reserved and committed before printing so the write
+    // lock isn't held while `lp` runs; a failed print hands them back.
+    let reserve_scheme = scheme.clone();
+    let (created, previous_last, reserved_last) = state
+        .with_db(move |conn| {
+            let scheme = reserve_scheme;
+            let tx = conn.transaction()?;
+            let previous_last: Option<i64> = tx
+                .query_row(
+                    "SELECT last FROM name_sequences WHERE scheme = ?1",
+                    params![scheme],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let mut created = Vec::new();
+            for _ in 0..count {
+                let name = next_container_name(&tx, &scheme)?;
+                tx.execute(
+                    "INSERT INTO containers (name, kind) VALUES (?1, 'bin')",
+                    params![name],
+                )?;
+                let id = tx.last_insert_rowid();
+                let after = container_snapshot(&tx, id)?;
+                record_event(
+                    &tx,
+                    EntityKind::Container,
+                    id,
+                    EventAction::Create,
+                    None,
+                    after,
+                    EventSource::WebForm,
+                )?;
+                created.push((id, name));
+            }
+            let reserved_last: i64 = tx.query_row(
+                "SELECT last FROM name_sequences WHERE scheme = ?1",
+                params![scheme],
+                |row| row.get(0),
+            )?;
+            tx.commit()?;
+            Ok((created, previous_last, reserved_last))
+        })
+        .await?;
+
+    let zpl: String = created
+        .iter()
+        .map(|(id, name)| zebra_label(&[], Some(name), state.config.container_url(*id).as_deref()))
+        .collect();
+    if let Err(e) = state.print_zpl(zpl).await {
+        let ids: Vec<i64> = created.iter().map(|(id, _)| *id).collect();
+        state
+            .with_db(move |conn| {
+                release_blank_bins(conn, &scheme, &ids, previous_last, reserved_last)
+            })
+            .await?;
+        return Err(AppError::Print(format!(
+            "The labels did not print: {e}. No bins were kept and no numbers were used up; try again once the printer is ready."
+        )));
+    }
+
+    let mut body = format!(
+        r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Blank bin labels</h1>
+    <p>Created {count} bin{plural} and sent their labels to the printer.</p><ul>"#,
+        plural = if count == 1 { "" } else { "s" },
+    );
+    let mut reprint = String::new();
+    for (id, name) in &created {
+        body.push_str(&format!(
+            r#"<li><a href="/containers/{id}">{}</a></li>"#,
+            html_escape(name)
+        ));
+        reprint.push_str(&format!(
+            r#"<input type="hidden" name="container_id" value="{id}">"#
+        ));
+    }
+    body.push_str(&format!(
+        r#"</ul>
+    <form method="post" action="/labels/print">{reprint}<button type="submit">Print them again</button></form>
+    <p style="margin-top: 1rem;"><a href="/containers">All containers</a> · <a href="/items">Back to Trove</a></p>"#
+    ));
+
+    Ok(Html(render_page("Blank bin labels", &body)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EntityKind {
+    Item,
+    Container,
+}
+
+impl EntityKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            EntityKind::Item => "item",
+            EntityKind::Container => "container",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum EventAction {
+    Create,
+    Update,
+    Move,
+    QuantityChange,
+    Delete,
+    Restore,
+    Purge,
+}
+
+impl EventAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            EventAction::Create => "create",
+            EventAction::Update => "update",
+            EventAction::Move => "move",
+            EventAction::QuantityChange => "quantity_change",
+            EventAction::Delete => "delete",
+            EventAction::Restore => "restore",
+            EventAction::Purge => "purge",
+        }
+    }
+}
+
+/// Where a change came from. The `events.source` column also reserves
+/// `'api'` for programmatic clients.
+#[derive(Debug, Clone, Copy)]
+enum EventSource {
+    WebForm,
+    LlmParse,
+}
+
+impl EventSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            EventSource::WebForm => "web_form",
+            EventSource::LlmParse => "llm_parse",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Event {
+    entity_type: String,
+    entity_id: i64,
+    action: String,
+    before: Option<String>,
+    after: Option<String>,
+    source: String,
+    created_at: String,
+}
+
+fn record_event(
+    conn: &Connection,
+    entity: EntityKind,
+    entity_id: i64,
+    action: EventAction,
+    before: Option<String>,
+    after: Option<String>,
+    source: EventSource,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO events (entity_type, entity_id, action, before_json, after_json, source)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            entity.as_str(),
+            entity_id,
+            action.as_str(),
+            before,
+            after,
+            source.as_str()
+        ],
+    )?;
+    Ok(())
+}
+
+fn load_item_tags(conn: &Connection, item_id: i64) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.name FROM item_tags it JOIN tags t ON t.id = it.tag_id
+         WHERE it.item_id = ?1
+         ORDER BY t.name",
+    )?;
+    let rows = stmt.query_map(params![item_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Replaces the item's tags with `tags`, creating any that are new.
+fn set_item_tags(conn: &Connection, item_id: i64, tags: &[String]) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM item_tags WHERE item_id = ?1", params![item_id])?;
+    for tag in tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+            params![tag],
+        )?;
+        conn.execute(
+            "INSERT INTO item_tags (item_id, tag_id)
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            params![item_id, tag],
+        )?;
+    }
+    Ok(())
+}
+
+fn load_items_with_tag(conn: &Connection, tag: &str) -> rusqlite::Result<Vec<ItemWithContainer>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"{ITEM_LIST_SELECT}
+        WHERE i.deleted_at IS NULL
+          AND EXISTS (SELECT 1 FROM item_tags it JOIN tags t ON t.id = it.tag_id
+                      WHERE it.item_id = i.id AND t.name = ?1)
+        ORDER BY c.name IS NULL, c.name, i.name COLLATE NOCASE
+        "#
+    ))?;
+
+    let rows = stmt.query_map(params![tag], item_list_row)?;
+    rows.collect()
+}
+
+fn load_item_attributes(
+    conn: &Connection,
+    item_id: i64,
+) -> rusqlite::Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT key, value FROM item_attributes WHERE item_id = ?1 ORDER BY key COLLATE NOCASE",
+    )?;
+    let rows = stmt.query_map(params![item_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+/// Attribute keys already used on any item, offered as suggestions.
+fn load_attribute_keys(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT DISTINCT key FROM item_attributes ORDER BY key COLLATE NOCASE")?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+fn load_photos(conn: &Connection, owner: EntityKind, id: i64) -> rusqlite::Result<Vec<Photo>> {
+    let owner_column = match owner {
+        EntityKind::Item => "item_id",
+        EntityKind::Container => "container_id",
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, file_name, thumb_name FROM photos WHERE {owner_column} = ?1 ORDER BY id"
+    ))?;
+    let rows = stmt.query_map(params![id], |row| {
+        Ok(Photo {
+            id: row.get(0)?,
+            file_name: row.get(1)?,
+            thumb_name: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// First-photo thumbnails for the items in one container, keyed by item id.
+fn load_item_thumbs(
+    conn: &Connection,
+    container_id: i64,
+) -> rusqlite::Result<HashMap<i64, String>> {
+    let mut stmt = conn.prepare(
+        "SELECT p.item_id, p.thumb_name
+         FROM photos p
+         JOIN items i ON i.id = p.item_id
+         WHERE i.container_id = ?1
+         ORDER BY p.id DESC",
+    )?;
+    let rows = stmt.query_map(params![container_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    // Descending order, so the earliest photo is inserted last and wins.
+    rows.collect()
+}
+
+fn record_movement(
+    conn: &Connection,
+    item_id: i64,
+    delta: f64,
+    reason: &str,
+    who: Option<&str>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO stock_movements (item_id, delta, reason, who) VALUES (?1, ?2, ?3, ?4)",
+        params![item_id, delta, reason, who],
+    )?;
+    Ok(())
+}
+
+fn load_movements(conn: &Connection, item_id: i64) -> rusqlite::Result<Vec<Movement>> {
+    let mut stmt = conn.prepare(
+        "SELECT delta, reason, who, created_at FROM stock_movements
+         WHERE item_id = ?1
+         ORDER BY id DESC",
+    )?;
+    let rows = stmt.query_map(params![item_id], |row| {
+        Ok(Movement {
+            delta: row.get(0)?,
+            reason: row.get(1)?,
+            who: row.get(2)?,
+            created_at: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// The item row as JSON, for the audit log. `None` if it doesn't exist.
+fn item_snapshot(conn: &Connection, id: i64) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT json_object(
+             'name', name,
+             'quantity', quantity,
+             'unit', unit,
+             'min_quantity', min_quantity,
+             'expires_on', expires_on,
+             'purchase_price', purchase_price,
+             'purchase_date', purchase_date,
+             'serial_number', serial_number,
+             'receipt_file', receipt_file,
+             'photos', (SELECT group_concat(file_name, ', ') FROM (
+                 SELECT file_name FROM photos WHERE item_id = items.id ORDER BY id)),
+             'parts', (SELECT group_concat(child_id || ' ' || kind || ' x' || quantity, ', ') FROM (
+                 SELECT child_id, kind, quantity FROM item_links
+                 WHERE parent_id = items.id ORDER BY child_id)),
+             'container_id', container_id,
+             'location', (SELECT name FROM locations WHERE id = items.location_id),
+             'deleted_at', deleted_at,
+             'tags', (SELECT group_concat(name, ', ') FROM (
+                 SELECT t.name FROM item_tags it JOIN tags t ON t.id = it.tag_id
+                 WHERE it.item_id = items.id ORDER BY t.name)),
+             'attributes', (SELECT json_group_object(key, value) FROM (
+                 SELECT key, value FROM item_attributes
+                 WHERE item_id = items.id ORDER BY key))
+         ) FROM items WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// The container row as JSON, for the audit log. `None` if it doesn't exist.
+fn container_snapshot(conn: &Connection, id: i64) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT json_object(
+             'name', name,
+             'kind', kind,
+             'parent_id', parent_id,
+             'grid', CASE WHEN grid_rows IS NOT NULL THEN grid_rows || 'x' || grid_cols END,
+             'cell', cell,
+             'photos', (SELECT group_concat(file_name, ', ') FROM (
+                 SELECT file_name FROM photos WHERE container_id = containers.id ORDER BY id)),
+             'location', (SELECT name FROM locations WHERE id = containers.location_id)
+         ) FROM containers WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Snapshot of whichever kind of entity `id` is.
+fn entity_snapshot(
+    conn: &Connection,
+    kind: EntityKind,
+    id: i64,
+) -> rusqlite::Result<Option<String>> {
+    match kind {
+        EntityKind::Item => item_snapshot(conn, id),
+        EntityKind::Container => container_snapshot(conn, id),
+    }
+}
+
+const EVENT_COLUMNS: &str =
+    "entity_type, entity_id, action, before_json, after_json, source, created_at";
+
+fn event_from_row(row: &rusqlite::Row) -> rusqlite::Result<Event> {
+    Ok(Event {
+        entity_type: row.get(0)?,
+        entity_id: row.get(1)?,
+        action: row.get(2)?,
+        before: row.get(3)?,
+        after: row.get(4)?,
+        source: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+fn load_events(conn: &Connection, entity: EntityKind, id: i64) -> rusqlite::Result<Vec<Event>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {EVENT_COLUMNS} FROM events
+         WHERE entity_type = ?1 AND entity_id = ?2
+         ORDER BY id DESC"
+    ))?;
+    let rows = stmt.query_map(params![entity.as_str(), id], event_from_row)?;
+    rows.collect()
+}
+
+/// The container's own events plus every item moving into or out of it.
+fn load_container_events(conn: &Connection, id: i64) -> rusqlite::Result<Vec<Event>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {EVENT_COLUMNS} FROM events
+         WHERE (entity_type = 'container' AND entity_id = ?1)
+            OR (entity_type = 'item'
+                AND (json_extract(before_json, '$.container_id') = ?1
+                  OR json_extract(after_json, '$.container_id') = ?1))
+         ORDER BY id DESC
+         LIMIT 100"
+    ))?;
+    let rows = stmt.query_map(params![id], event_from_row)?;
+    rows.collect()
+}
+
+fn render_timeline(events: &[Event]) -> String {
+    let mut html =
+        String::from(r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">History</h2>"#);
+
+    if events.is_empty() {
+        html.push_str("<p><em>No recorded changes.</em></p>");
+        return html;
+    }
+
+    html.push_str(r#"<ul style="font-size: 0.85rem; padding-left: 1.2rem;">"#);
+    for e in events {
+        let before: Option<serde_json::Value> = e
+            .before
+            .as_deref()
+            .and_then(|s| serde_json::from_str(s).ok());
+        let after: Option<serde_json::Value> = e
+            .after
+            .as_deref()
+            .and_then(|s| serde_json::from_str(s).ok());
+
+        let subject = after
+            .as_ref()
+            .or(before.as_ref())
+            .and_then(|v| v.get("name"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let subject = if e.entity_type == EntityKind::Item.as_str() {
+            format!(
+                r#"<a href="/items/{}/edit">{}</a>"#,
+                e.entity_id,
+                html_escape(subject)
+            )
+        } else {
+            html_escape(subject)
+        };
+
+        let mut changes = Vec::new();
+        if let (Some(serde_json::Value::Object(b)), Some(serde_json::Value::Object(a))) =
+            (&before, &after)
+        {
+            for (key, new) in a {
+                let old = b.get(key).unwrap_or(&serde_json::Value::Null);
+                if old != new {
+                    changes.push(format!(
+                        "{}: {} → {}",
+                        key,
+                        json_display(old),
+                        json_display(new)
+                    ));
+                }
+            }
+        }
+
+        html.push_str(&format!(
+            r#"<li><span style="color: gray;">{when}</span> {action} {subject} <small style="color: gray;">({source})</small>"#,
+            when = html_escape(&e.created_at),
+            action = html_escape(&e.action.replace('_', " ")),
+            source = html_escape(&e.source.replace('_', " ")),
+        ));
+        if !changes.is_empty() {
+            html.push_str(&format!(
+                "<br><small>{}</small>",
+                html_escape(&changes.join("; "))
+            ));
+        }
+        html.push_str("</li>");
+    }
+    html.push_str("</ul>");
+
+    html
+}
+
+fn json_display(v: &serde_json::Value) -> String {
+    match v {
+        serde_json::Value::Null => "—".to_string(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Longest edge of generated thumbnails, in pixels.
+const THUMBNAIL_SIZE: u32 = 240;
+
+/// Phone camera photos routinely exceed axum's 2 MB default body limit.
+const MAX_UPLOAD_BYTES: usize = 32 * 1024 * 1024;
+
+#[derive(Debug)]
+struct Photo {
+    id: i64,
+    file_name: String,
+    thumb_name: String,
+}
+
+/// A decoded upload: the original bytes plus a JPEG thumbnail.
+struct ProcessedPhoto {
+    original: Vec<u8>,
+    extension: &'static str,
+    thumbnail: Vec<u8>,
+}
+
+async fn handle_upload_item_photos(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    multipart: Multipart,
+) -> Result<Redirect, AppError> {
+    upload_photos(&state, EntityKind::Item, id, multipart).await?;
+    Ok(Redirect::to(&format!("/items/{id}/edit")))
+}
+
+async fn handle_upload_container_photos(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    multipart: Multipart,
+) -> Result<Redirect, AppError> {
+    upload_photos(&state, EntityKind::Container, id, multipart).await?;
+    Ok(Redirect::to(&format!("/containers/{id}")))
+}
+
+/// Reads every `photo` field, thumbnails it, then stores rows and files in
+/// one transaction so a failed write leaves no orphan rows.
+async fn upload_photos(
+    state: &AppState,
+    owner: EntityKind,
+    owner_id: i64,
+    mut multipart: Multipart,
+) -> Result<(), AppError> {
+    let upload_error = |e: axum::extract::multipart::MultipartError| {
+        AppError::BadRequest(format!("The upload didn't come through: {e}"))
+    };
+
+    let mut uploads = Vec::new();
+    while let Some(field) = multipart.next_field().await.map_err(upload_error)? {
+        if field.name() != Some("photo") {
+            continue;
+        }
+        let data = field.bytes().await.map_err(upload_error)?;
+        if !data.is_empty() {
+            uploads.push(data);
+        }
+    }
+    if uploads.is_empty() {
+        return Err(AppError::BadRequest(
+            "Choose a photo to upload.".to_string(),
+        ));
+    }
+
+    let processed = tokio::task::spawn_blocking(move || {
+        uploads
+            .iter()
+            .map(|data| process_photo(data))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .await??;
+
+    let media_dir = PathBuf::from(&state.config.media_dir);
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let owner_column = match owner {
+                EntityKind::Item => {
+                    load_item(&tx, owner_id)?;
+                    "item_id"
+                }
+                EntityKind::Container => {
+                    load_container(&tx, owner_id)?;
+                    "container_id"
+                }
+            };
+            let before = entity_snapshot(&tx, owner, owner_id)?;
+
+            let mut written = Vec::new();
+            for photo in processed {
+                tx.execute(
+                    &format!(
+                        "INSERT INTO photos ({owner_column}, file_name, thumb_name)
+                         VALUES (?1, '', '')"
+                    ),
+                    params![owner_id],
+                )?;
+                let photo_id = tx.last_insert_rowid();
+                let stem = format!("{}-{owner_id}-{photo_id}", owner.as_str());
+                let stored = Photo {
+                    id: photo_id,
+                    file_name: format!("{stem}.{}", photo.extension),
+                    thumb_name: format!("{stem}-thumb.jpg"),
+                };
+                tx.execute(
+                    "UPDATE photos SET file_name = ?1, thumb_name = ?2 WHERE id = ?3",
+                    params![stored.file_name, stored.thumb_name, photo_id],
+                )?;
+
+                let result = std::fs::write(media_dir.join(&stored.file_name), &photo.original)
+                    .and_then(|()| {
+                        std::fs::write(media_dir.join(&stored.thumb_name), &photo.thumbnail)
+                    });
+                written.push(stored);
+                if let Err(e) = result {
+                    remove_photo_files(&media_dir, &written);
+                    return Err(e.into());
+                }
+            }
+
+            let after = entity_snapshot(&tx, owner, owner_id)?;
+            let logged = record_event(
+                &tx,
+                owner,
+                owner_id,
+                EventAction::Update,
+                before,
+                after,
+                EventSource::WebForm,
+            )
+            .and_then(|()| tx.commit());
+            if let Err(e) = logged {
+                remove_photo_files(&media_dir, &written);
+                return Err(e.into());
+            }
+            Ok(())
+        })
+        .await
+}
+
+/// Decodes an upload (honouring EXIF rotation) and renders its thumbnail.
+fn process_photo(data: &[u8]) -> Result<ProcessedPhoto, AppError> {
+    use image::ImageDecoder;
+
+    let unsupported = |_| {
+        AppError::BadRequest(
+            "That file isn't a supported image (JPEG, PNG, WebP or GIF).".to_string(),
+        )
+    };
+
+    let format = image::guess_format(data).map_err(unsupported)?;
+    let extension = format.extensions_str().first().copied().unwrap_or("img");
+
+    let mut decoder = image::ImageReader::with_format(Cursor::new(data), format)
+        .into_decoder()
+        .map_err(unsupported)?;
+    let orientation = decoder.orientation().map_err(unsupported)?;
+    let mut img = image::DynamicImage::from_decoder(decoder).map_err(unsupported)?;
+    img.apply_orientation(orientation);
+
+    let thumb =
+        image::DynamicImage::ImageRgb8(img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8());
+    let mut thumbnail = Cursor::new(Vec::new());
+    thumb
+        .write_to(&mut thumbnail, image::ImageFormat::Jpeg)
+        .map_err(|e| AppError::Io(std::io::Error::other(e)))?;
+
+    Ok(ProcessedPhoto {
+        original: data.to_vec(),
+        extension,
+        thumbnail: thumbnail.into_inner(),
+    })
+}
+
+async fn handle_delete_photo(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Redirect, AppError> {
+    let media_dir = PathBuf::from(&state.config.media_dir);
+    let back = state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            let (photo, item_id, container_id) = tx
+                .query_row(
+                    "SELECT id, file_name, thumb_name, item_id, container_id
+                     FROM photos WHERE id = ?1",
+                    params![id],
+                    |row| {
+                        Ok((
+                            Photo {
+                                id: row.get(0)?,
+                                file_name: row.get(1)?,
+                                thumb_name: row.get(2)?,
+                            },
+                            row.get::<_, Option<i64>>(3)?,
+                            row.get::<_, Option<i64>>(4)?,
+                        ))
+                    },
+                )
+                .optional()?
+                .ok_or_else(|| AppError::NotFound(format!("Photo #{id}")))?;
+
+            let owner = match (item_id, container_id) {
+                (Some(item_id), _) => Some((EntityKind::Item, item_id)),
+                (_, Some(container_id)) => Some((EntityKind::Container, container_id)),
+                _ => None,
+            };
+            let before = match owner {
+                Some((kind, owner_id)) => entity_snapshot(&tx, kind, owner_id)?,
+                None => None,
+            };
+            tx.execute("DELETE FROM photos WHERE id = ?1", params![id])?;
+            if let Some((kind, owner_id)) = owner {
+                let after = entity_snapshot(&tx, kind, owner_id)?;
+                record_event(
+                    &tx,
+                    kind,
+                    owner_id,
+                    EventAction::Update,
+                    before,
+                    after,
+                    EventSource::WebForm,
+                )?;
+            }
+            tx.commit()?;
+            remove_photo_files(&media_dir, std::slice::from_ref(&photo));
+
+            Ok(match (item_id, container_id) {
+                (Some(item_id), _) => format!("/items/{item_id}/edit"),
+                (_, Some(container_id)) => format!("/containers/{container_id}"),
+                _ => "/items".to_string(),
+            })
+        })
+        .await?;
+
+    Ok(Redirect::to(&back))
+}
+
+/// Best-effort cleanup; a leftover file is harmless, so failures are logged.
+fn remove_photo_files(media_dir: &FsPath, photos: &[Photo]) {
+    for photo in photos {
+        for name in [&photo.file_name, &photo.thumb_name] {
+            remove_media_file(media_dir, name);
+        }
+    }
+}
+
+fn remove_media_file(media_dir: &FsPath, name: &str) {
+    if let Err(e) = std::fs::remove_file(media_dir.join(name)) {
+        eprintln!("Failed to remove media file {name}: {e}");
+    }
+}
+
+/// Purchase details kept for insurance claims.
+#[derive(Debug, Default)]
+struct Purchase {
+    /// Per unit of the item's quantity.
+    price: Option<f64>,
+    date: Option<String>,
+    serial_number: Option<String>,
+    /// File name in the media directory.
+    receipt_file: Option<String>,
+}
+
+/// Reads purchase details whether or not the item is in the trash.
+fn load_purchase(conn: &Connection, id: i64) -> Result<Purchase, AppError> {
+    conn.query_row(
+        "SELECT purchase_price, purchase_date, serial_number, receipt_file
+         FROM items WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(Purchase {
+                price: row.get(0)?,
+                date: row.get(1)?,
+                serial_number: row.get(2)?,
+                receipt_file: row.get(3)?,
+            })
+        },
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("Item #{id}")))
+}
+
+/// Receipts may be PDFs as well as photos; anything else is refused.
+fn receipt_extension(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"%PDF") {
+        return Some("pdf");
+    }
+    match image::guess_format(data).ok()? {
+        image::ImageFormat::Jpeg => Some("jpg"),
+        image::ImageFormat::Png => Some("png"),
+        image::ImageFormat::WebP => Some("webp"),
+        image::ImageFormat::Gif => Some("gif"),
+        _ => None,
+    }
+}
+
+/// Stores the `receipt` field as `receipt-{id}.{ext}`, replacing any earlier one.
+async fn handle_upload_receipt(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    mut multipart: Multipart,
+) -> Result<Redirect, AppError> {
+    let upload_error = |e: axum::extract::multipart::MultipartError| {
+        AppError::BadRequest(format!("The upload didn't come through: {e}"))
+    };
+
+    let mut upload = None;
+    while let Some(field) = multipart.next_field().await.map_err(upload_error)? {
+        if field.name() == Some("receipt") {
+            let data = field.bytes().await.map_err(upload_error)?;
+            if !data.is_empty() {
+                upload = Some(data);
+            }
+        }
+    }
+    let data =
+        upload.ok_or_else(|| AppError::BadRequest("Choose a receipt to upload.".to_string()))?;
+    let extension = receipt_extension(&data).ok_or_else(|| {
+        AppError::BadRequest(
+            "Receipts must be a PDF or an image (JPEG, PNG, WebP or GIF).".to_string(),
+        )
+    })?;
+
+    let media_dir = PathBuf::from(&state.config.media_dir);
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            load_item(&tx, id)?;
+            let old = load_purchase(&tx, id)?.receipt_file;
+            let file_name = format!("receipt-{id}.{extension}");
+
+            let before = item_snapshot(&tx, id)?;
+            tx.execute(
+                "UPDATE items SET receipt_file = ?1 WHERE id = ?2",
+                params![file_name, id],
+            )?;
+            let after = item_snapshot(&tx, id)?;
+            record_event(
+                &tx,
+                EntityKind::Item,
+                id,
+                EventAction::Update,
+                before,
+                after,
+                EventSource::WebForm,
+            )?;
+
+            std::fs::write(media_dir.join(&file_name), &data)?;
+            tx.commit()?;
+            if let Some(old) = old.filter(|old| *old != file_name) {
+                remove_media_file(&media_dir, &old);
+            }
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to(&format!("/items/{id}/edit")))
+}
+
+async fn handle_delete_receipt(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Redirect, AppError> {
+    let media_dir = PathBuf::from(&state.config.media_dir);
+    state
+        .with_db(move |conn| {
+            let tx = conn.transaction()?;
+            load_item(&tx, id)?;
+            let Some(old) = load_purchase(&tx, id)?.receipt_file else {
+                return Ok(());
+            };
+
+            let before = item_snapshot(&tx, id)?;
+            tx.execute(
+                "UPDATE items SET receipt_file = NULL WHERE id = ?1",
+                params![id],
+            )?;
+            let after = item_snapshot(&tx, id)?;
+            record_event(
+                &tx,
+                EntityKind::Item,
+                id,
+                EventAction::Update,
+                before,
+                after,
+                EventSource::WebForm,
+            )?;
+            tx.commit()?;
+            remove_media_file(&media_dir, &old);
+            Ok(())
+        })
+        .await?;
+
+    Ok(Redirect::to(&format!("/items/{id}/edit")))
+}
+
+fn render_receipt(id: i64, purchase: &Purchase) -> String {
+    let mut html =
+        String::from(r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Receipt</h2>"#);
+    if let Some(ref file) = purchase.receipt_file {
+        html.push_str(&format!(
+            r#"<p><a href="/media/{file}">View receipt</a>
+      <form method="post" action="/items/{id}/receipt/delete" style="display: inline;"><button type="submit">remove</button></form></p>"#,
+            file = html_escape(file),
+        ));
+    }
+    html.push_str(&format!(
+        r#"<form method="post" action="/items/{id}/receipt" enctype="multipart/form-data">
+      <input name="receipt" type="file" accept="application/pdf,image/*">
+      <button type="submit">{verb}</button>
+    </form>"#,
+        verb = if purchase.receipt_file.is_some() {
+            "Replace"
+        } else {
+            "Upload"
+        },
+    ));
+    html
+}
+
+fn render_thumb(thumb_name: &str, height: &str) -> String {
+    format!(
+        r#"<img src="/media/{}" alt="" style="height: {height}; vertical-align: middle; margin-right: 0.25rem;">"#,
+        html_escape(thumb_name)
+    )
+}
+
+/// Photo gallery with per-photo delete buttons and a camera-friendly upload form.
+fn render_photos(photos: &[Photo], upload_action: &str) -> String {
+    let mut html =
+        String::from(r#"<h2 style="font-size: 1.1rem; margin-top: 1.5rem;">Photos</h2>"#);
+
+    if !photos.is_empty() {
+        html.push_str(r#"<div style="display: flex; flex-wrap: wrap; gap: 0.5rem;">"#);
+        for p in photos {
+            html.push_str(&format!(
+                r#"<div style="text-align: center;">
+          <a href="/media/{file}"><img src="/media/{thumb}" alt="photo" style="height: 120px; display: block;"></a>
+          <form method="post" action="/photos/{id}/delete"><button type="submit">remove</button></form>
+        </div>"#,
+                file = html_escape(&p.file_name),
+                thumb = html_escape(&p.thumb_name),
+                id = p.id,
+            ));
+        }
+        html.push_str("</div>");
+    }
+
+    html.push_str(&format!(
+        r#"<form method="post" action="{action}" enctype="multipart/form-data" style="margin-top: 0.5rem;">
+      <input type="file" name="photo" accept="image/*" capture="environment" multiple>
+      <button type="submit">Upload</button>
+    </form>"#,
+        action = html_escape(upload_action),
+    ));
+
+    html
+}
+
+async fn show_tags(State(state): State<AppState>) -> Result<Html<String>, AppError> {
+    let tags = state
+        .with_db(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT t.name, COUNT(i.id)
+                 FROM tags t
+                 JOIN item_tags it ON it.tag_id = t.id
+                 JOIN items i ON i.id = it.item_id AND i.deleted_at IS NULL
+                 GROUP BY t.id
+                 ORDER BY t.name",
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+            Ok(rows.collect::<rusqlite::Result<Vec<(String, i64)>>>()?)
+        })
+        .await?;
+
+    let mut body =
+        String::from(r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Tags</h1>"#);
+    if tags.is_empty() {
+        body.push_str("<p><em>No tags yet. Add some from an item's edit page.</em></p>");
+    } else {
+        body.push_str("<p>");
+        for (name, count) in &tags {
+            body.push_str(&render_tag_chip(name));
+            body.push_str(&format!(r#"<small style="color: gray;"> {count}</small> "#));
+        }
+        body.push_str("</p>");
+    }
+    body.push_str(r#"<p style="margin-top: 1rem;"><a href="/items">Back to Trove</a></p>"#);
+
+    Ok(Html(render_page("Tags", &body)))
+}
+
+async fn show_tag(
+    State(state): State<AppState>,
+    Path(tag): Path<String>,
+) -> Result<Html<String>, AppError> {
+    let tag = normalize_tag(&tag);
+    let lookup = tag.clone();
+    let items = state
+        .with_db(move |conn| Ok(load_items_with_tag(conn, &lookup)?))
+        .await?;
+
+    let mut body = format!(
+        r#"<h1 style="font-size: 1.4rem; margin-bottom: 0.75rem;">Tagged “{}”</h1>"#,
+        html_escape(&tag)
+    );
+
+    if items.is_empty() {
+        body.push_str("<p><em>Nothing has this tag.</em></p>");
+    } else {
+        body.push_str(
+            r#"<table style="width: 100%; border-collapse: collapse; font-size: 0.9rem;"><tbody>"#,
+        );
+        for row in &items {
+            let item = &row.item;
+            let mut line = row
+                .thumb
+                .as_deref()
+                .map(|thumb| render_thumb(thumb, "2rem"))
+                .unwrap_or_default();
+            line.push_str(&format_amount(
+                item.quantity,
+                &item.unit,
+                &html_escape(&item.name),
+            ));
+            match (item.container_id, &row.container_name) {
+                (Some(cid), Some(name)) => line.push_str(&format!(
+                    r#" — <a href="/containers/{cid}">{}</a>"#,
+                    html_escape(name)
+                )),
+                _ => line.push_str(" — loose"),
+            }
+            if let Some(ref loc) = item.location {
+                line.push_str(&format!(" — {}", html_escape(loc)));
+            }
+            for other in row.tags.iter().filter(|t| **t != tag) {
+                line.push_str(&render_tag_chip(other));
+            }
+            body.push_str(&format!(
+                r#"<tr>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee;">{line}</td>
+          <td style="padding: 2px 4px; border-top: 1px solid #eee; text-align: right;"><a href="/items/{id}/edit">edit</a></td>
+        </tr>"#,
+                id = item.id,
+            ));
+        }
+        body.push_str("</tbody></table>");
+    }
+
+    body.push_str(
+        r#"<p style="margin-top: 1rem;"><a href="/tags">All tags</a> · <a href="/items">Back to Trove</a></p>"#,
+    );
+
+    Ok(Html(render_page(&format!("Tag: {tag}"), &body)))
+}
+
+fn render_tag_chip(tag: &str) -> String {
+    format!(
+        r#" <a href="/tags/{}" style="font-size: 0.75rem; border: 1px solid #999; border-radius: 0.6rem; padding: 0 0.4rem; text-decoration: none; color: inherit; white-space: nowrap;">{}</a>"#,
+        url_encode(tag),
+        html_escape(tag)
+    )
+}
+
+/// Lowercases and collapses whitespace so "Wood  Turning" and "wood turning" match.
+fn normalize_tag(tag: &str) -> String {
+    normalize_name(tag)
+}
+
+/// Splits the comma-separated tag field, dropping blanks and duplicates.
+fn parse_tags(raw: &str) -> Vec<String> {
+    let mut tags: Vec<String> = raw
+        .split(',')
+        .map(normalize_tag)
+        .filter(|t| !t.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+fn split_tag_list(list: Option<String>) -> Vec<String> {
+    list.map(|l| l.split(',').map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+#[derive(Debug)]
+struct ItemWithContainer {
+    item: Item,
+    container_name: Option<String>,
+    /// Thumbnail of the item's first photo, if it has any.
+    thumb: Option<String>,
+    tags: Vec<String>,
+    attributes: Vec<(String, String)>,
+    /// Borrowers with an open loan, comma-separated.
+    on_loan_to: Option<String>,
+}
+
+/// Columns and joins shared by every item listing; callers append the
+/// WHERE and ORDER BY clauses and map rows with `item_list_row`.
+const ITEM_LIST_SELECT: &str = r#"
+    SELECT
+        i.id,
+        i.name,
+        i.quantity,
+        i.container_id,
+        il.name,
+        c.name,
+        (SELECT p.thumb_name FROM photos p WHERE p.item_id = i.id ORDER BY p.id LIMIT 1),
+        (SELECT group_concat(name, ',') FROM (
+            SELECT t.name FROM item_tags it JOIN tags t ON t.id = it.tag_id
+            WHERE it.item_id = i.id ORDER BY t.name)),
+        (SELECT json_group_array(json_array(key, value)) FROM (
+            SELECT key, value FROM item_attributes a
+            WHERE a.item_id = i.id ORDER BY key)),
+        i.unit,
+        i.min_quantity,
+        i.expires_on,
+        (SELECT group_concat(borrower, ', ') FROM loans l
+         WHERE l.item_id = i.id AND l.returned_at IS NULL),
+        i.location_id,
+        (SELECT coalesce(sum(quantity), 0) FROM loans l
+         WHERE l.item_id = i.id AND l.returned_at IS NULL)
+    FROM items i
+    LEFT JOIN containers c ON i.container_id = c.id
+    LEFT JOIN item_locations il ON il.item_id = i.id
+"#;
+
+fn item_list_row(row: &rusqlite::Row) -> rusqlite::Result<ItemWithContainer> {
+    let attributes: Option<String> = row.get(8)?;
+    Ok(ItemWithContainer {
+        item: Item {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            quantity: row.get(2)?,
+            unit: row.get(9)?,
+            min_quantity: row.get(10)?,
+            expires_on: row.get(11)?,
+            container_id: row.get(3)?,
+            location: row.get(4)?,
+            location_id: row.get(13)?,
+            lent: row.get(14)?,
+        },
+        container_name: row.get(5)?,
+        thumb: row.get(6)?,
+        tags: split_tag_list(row.get(7)?),
+        attributes: attributes
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        on_loan_to: row.get(12)?,
+    })
+}
+
+/// Every live item, optionally narrowed to those whose name, location,
+/// container, tags or attributes contain `search`.
+fn load_items_from_db(
+    conn: &Connection,
+    search: Option<&str>,
+) -> rusqlite::Result<Vec<ItemWithContainer>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"{ITEM_LIST_SELECT}
+        WHERE i.deleted_at IS NULL
+          AND (?1 IS NULL
+               OR i.name LIKE ?1 ESCAPE '\'
+               OR il.name LIKE ?1 ESCAPE '\'
+               OR c.name LIKE ?1 ESCAPE '\'
+               OR EXISTS (SELECT 1 FROM item_tags it JOIN tags t ON t.id = it.tag_id
+                          WHERE it.item_id = i.id AND t.name LIKE ?1 ESCAPE '\')
+               OR EXISTS (SELECT 1 FROM item_attributes a
+                          WHERE a.item_id = i.id
+                            AND (a.key LIKE ?1 ESCAPE '\' OR a.value LIKE ?1 ESCAPE '\')))
+        ORDER BY 
+            c.name IS NULL,    -- containers first, loose items last
+            c.name ASC,
+            datetime(i.created_at) DESC
+        "#
+    ))?;
+
+    let pattern = search.map(like_pattern);
+    let rows = stmt.query_map(params![pattern], item_list_row)?;
+
+    let mut items = Vec::new();
+    for row_result in rows {
+        items.push(row_result?);
+    }
+
+    Ok(items)
+}
+
+/// `%s%` with LIKE wildcards in `s` escaped, for `LIKE ?1 ESCAPE '\'`.
+fn like_pattern(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// Wraps `body` in the shared page chrome used by the simpler pages.
+fn render_page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <title>{title}</title>
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+  </head>
+  <body style="font-family: serif; padding: 1rem; max-width: 600px; margin: 0 auto;">
+{body}
+  </body>
+</html>"#,
+        title = html_escape(title),
+    )
+}
+
+/// Percent-encodes `s` for use as a single URL path segment.
+fn url_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn load_trashed_items(conn: &Connection) -> rusqlite::Result<Vec<TrashedItem>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT i.id, i.name, i.quantity, i.container_id, il.name, c.name, i.deleted_at,
+               i.unit, i.min_quantity, i.expires_on, i.location_id, {LENT_SQL}
+        FROM items i
+        LEFT JOIN containers c ON i.container_id = c.id
+        LEFT JOIN item_locations il ON il.item_id = i.id
+        WHERE i.deleted_at IS NOT NULL
+        ORDER BY datetime(i.deleted_at) DESC
+        "#
+    ))?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok(TrashedItem {
+            item: Item {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                quantity: row.get(2)?,
+                unit: row.get(7)?,
+                min_quantity: row.get(8)?,
+                expires_on: row.get(9)?,
+                container_id: row.get(3)?,
+                location: row.get(4)?,
+                location_id: row.get(10)?,
+                lent: row.get(11)?,
+            },
+            container_name: row.get(5)?,
+            deleted_at: row.get(6)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+fn load_item(conn: &Connection, id: i64) -> Result<Item, AppError> {
+    conn.query_row(
+        &format!(
+            "SELECT id, name, quantity, container_id,
+                    (SELECT name FROM item_locations WHERE item_id = i.id),
+                    unit, min_quantity, expires_on, location_id, {LENT_SQL}
+             FROM items i
+             WHERE id = ?1 AND deleted_at IS NULL"
+        ),
+        params![id],
+        |row| {
+            Ok(Item {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                quantity: row.get(2)?,
+                unit: row.get(5)?,
+                min_quantity: row.get(6)?,
+                expires_on: row.get(7)?,
+                container_id: row.get(3)?,
+                location: row.get(4)?,
+                location_id: row.get(8)?,
+                lent: row.get(9)?,
+            })
+        },
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("Item #{id}")))
+}
+
+/// Columns read by `container_row`, in order.
+const CONTAINER_COLUMNS: &str =
+    "id, name, kind, parent_id, location_id, grid_rows, grid_cols, cell";
+
+fn container_row(row: &rusqlite::Row) -> rusqlite::Result<Container> {
+    let rows: Option<i64> = row.get(5)?;
+    let cols: Option<i64> = row.get(6)?;
+    Ok(Container {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        kind: row.get(2)?,
+        parent_id: row.get(3)?,
+        location_id: row.get(4)?,
+        grid: rows.zip(cols),
+        cell: row.get(7)?,
+    })
+}
+
+fn load_containers(conn: &Connection) -> rusqlite::Result<Vec<Container>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {CONTAINER_COLUMNS} FROM containers ORDER BY name"
+    ))?;
+
+    let rows = stmt.query_map([], container_row)?;
+
+    let mut containers = Vec::new();
+    for r in rows {
+        containers.push(r?);
+    }
+    Ok(containers)
+}
+
+fn load_container(conn: &Connection, id: i64) -> Result<Container, AppError> {
+    conn.query_row(
+        &format!("SELECT {CONTAINER_COLUMNS} FROM containers WHERE id = ?1"),
+        params![id],
+        container_row,
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("Container #{id}")))
+}
+
+fn load_container_summaries(conn: &Connection) -> rusqlite::Result<Vec<ContainerSummary>> {
+    // A grid's count includes what's in its drawers.
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT c.id, c.name, c.kind, c.parent_id, c.location_id, c.grid_rows, c.grid_cols, c.cell,
+               (SELECT COUNT(*) FROM items i
+                WHERE i.deleted_at IS NULL
+                  AND (i.container_id = c.id
+                       OR i.container_id IN (SELECT d.id FROM containers d
+                                             WHERE d.parent_id = c.id AND d.cell IS NOT NULL))),
+               (SELECT l.name FROM container_locations cl JOIN locations l ON l.id = cl.location_id
+                WHERE cl.container_id = c.id)
+        FROM containers c
+        ORDER BY c.name
+        "#,
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok(ContainerSummary {
+            container: container_row(row)?,
+            item_count: row.get(8)?,
+            location: row.get(9)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+fn load_container_items(conn: &Connection, container_id: i64) -> rusqlite::Result<Vec<Item>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT id, name, quantity, container_id,
+               (SELECT name FROM item_locations WHERE item_id = i.id),
+               unit, min_quantity, expires_on, location_id, {LENT_SQL}
+        FROM items i
+        WHERE container_id = ?1 AND deleted_at IS NULL
+        ORDER BY name COLLATE NOCASE
+        "#
+    ))?;
+
+    let rows = stmt.query_map(params![container_id], |row| {
+        Ok(Item {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            quantity: row.get(2)?,
+            unit: row.get(5)?,
+            min_quantity: row.get(6)?,
+            expires_on: row.get(7)?,
+            container_id: row.get(3)?,
+            location: row.get(4)?,
+            location_id: row.get(8)?,
+            lent: row.get(9)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+#[derive(Debug)]
+enum SaveOutcome {
+    Inserted(i64),
+    /// Added onto an existing row; `quantity` is that row's new total, in
+    /// that row's `unit`.
+    Merged {
+        id: i64,
+        quantity: f64,
+        unit: String,
+    },
 }
 
-fn print_zebra_label(
+/// Inserts `items`, or with `merge` set, adds each onto an existing row with
+/// the same normalized name in the same container and location whose unit
+/// the new amount can be converted to. Locations are compared as the items
+/// would show them, so one inherited from the container matches one named.
+fn save_items_tx(
+    tx: &rusqlite::Transaction,
     items: &[Item],
-    container_name: Option<&str>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let header = match container_name {
-        Some(name) => name,
-        None => "",
+    merge: bool,
+    source: EventSource,
+) -> rusqlite::Result<Vec<SaveOutcome>> {
+    let mut insert = tx.prepare(
+        "INSERT INTO items
+             (name, quantity, unit, min_quantity, expires_on, container_id, location_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    let mut candidates = tx.prepare(
+        "SELECT i.id, i.name, i.unit FROM items i
+         LEFT JOIN item_locations il ON il.item_id = i.id
+         WHERE i.container_id IS ?1 AND i.deleted_at IS NULL
+           AND il.location_id IS coalesce(?2,
+               (SELECT location_id FROM container_locations WHERE container_id IS ?1))
+         ORDER BY i.id",
+    )?;
+
+    let mut outcomes = Vec::with_capacity(items.len());
+    for item in items {
+        let existing = if merge {
+            let key = normalize_name(&item.name);
+            candidates
+                .query_map(params![item.container_id, item.location_id], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+                .into_iter()
+                .filter(|(_, name, _)| normalize_name(name) == key)
+                .find_map(|(id, _, unit)| {
+                    convert_quantity(item.quantity, &item.unit, &unit).map(|q| (id, q, unit))
+                })
+        } else {
+            None
+        };
+
+        let outcome = match existing {
+            Some((id, added, unit)) => {
+                let before = item_snapshot(tx, id)?;
+                let quantity = tx.query_row(
+                    "UPDATE items SET quantity = quantity + ?1 WHERE id = ?2 RETURNING quantity",
+                    params![added, id],
+                    |row| row.get(0),
+                )?;
+                // Mixed batches are only as good as the one that goes off first.
+                if let Some(date) = &item.expires_on {
+                    tx.execute(
+                        "UPDATE items SET expires_on = ?1
+                         WHERE id = ?2 AND (expires_on IS NULL OR expires_on > ?1)",
+                        params![date, id],
+                    )?;
+                }
+                // A threshold given with the new entry replaces the old one.
+                if let Some(min) = item
+                    .min_quantity
+                    .and_then(|m| convert_quantity(m, &item.unit, &unit))
+                {
+                    tx.execute(
+                        "UPDATE items SET min_quantity = ?1 WHERE id = ?2",
+                        params![min, id],
+                    )?;
+                }
+                let reason = if unit == item.unit {
+                    "added".to_string()
+                } else {
+                    format!("added ({})", format_quantity(item.quantity, &item.unit))
+                };
+                record_movement(tx, id, added, &reason, None)?;
+                let after = item_snapshot(tx, id)?;
+                record_event(
+                    tx,
+                    EntityKind::Item,
+                    id,
+                    EventAction::QuantityChange,
+                    before,
+                    after,
+                    source,
+                )?;
+                SaveOutcome::Merged { id, quantity, unit }
+            }
+            None => {
+                insert.execute(params![
+                    &item.name,
+                    item.quantity,
+                    &item.unit,
+                    item.min_quantity,
+                    &item.expires_on,
+                    item.container_id,
+                    item.location_id,
+                ])?;
+                let id = tx.last_insert_rowid();
+                record_movement(tx, id, item.quantity, "added", None)?;
+                let after = item_snapshot(tx, id)?;
+                record_event(
+                    tx,
+                    EntityKind::Item,
+                    id,
+                    EventAction::Create,
+                    None,
+                    after,
+                    source,
+                )?;
+                SaveOutcome::Inserted(id)
+            }
+        };
+        outcomes.push(outcome);
+    }
+
+    Ok(outcomes)
+}
+
+/// Case- and whitespace-insensitive key used to spot duplicate item names.
+fn normalize_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Units offered in forms and to the LLM. Anything else typed in is kept as
+/// written (lowercased) but never converted.
+const COMMON_UNITS: &[&str] = &[
+    "each", "m", "cm", "mm", "ft", "in", "kg", "g", "lb", "oz", "l", "ml", "box", "roll", "pack",
+    "pair",
+];
+
+/// Slack for float sums when comparing quantities and ledger totals.
+const QUANTITY_EPSILON: f64 = 1e-6;
+
+/// Maps spellings like "metres", "lbs" or "pcs" onto the short form used in
+/// `COMMON_UNITS`. Blank means "each".
+fn canonical_unit(raw: &str) -> String {
+    let unit = raw.trim().trim_end_matches('.').to_lowercase();
+    let canonical = match unit.as_str() {
+        "" | "each" | "ea" | "x" | "pc" | "pcs" | "piece" | "pieces" | "count" => "each",
+        "m" | "meter" | "meters" | "metre" | "metres" => "m",
+        "cm" | "centimeter" | "centimeters" | "centimetre" | "centimetres" => "cm",
+        "mm" | "millimeter" | "millimeters" | "millimetre" | "millimetres" => "mm",
+        "ft" | "foot" | "feet" => "ft",
+        "in" | "inch" | "inches" => "in",
+        "kg" | "kgs" | "kilo" | "kilos" | "kilogram" | "kilograms" => "kg",
+        "g" | "gram" | "grams" => "g",
+        "lb" | "lbs" | "pound" | "pounds" => "lb",
+        "oz" | "ounce" | "ounces" => "oz",
+        "l" | "liter" | "liters" | "litre" | "litres" => "l",
+        "ml" | "milliliter" | "milliliters" | "millilitre" | "millilitres" => "ml",
+        "box" | "boxes" => "box",
+        "roll" | "rolls" => "roll",
+        "pack" | "packs" | "pk" => "pack",
+        "pair" | "pairs" => "pair",
+        _ => return unit,
     };
+    canonical.to_string()
+}
+
+/// Dimension and size in that dimension's base unit (metre, kilogram, litre)
+/// for units that can be converted; counted units have none.
+fn unit_scale(unit: &str) -> Option<(&'static str, f64)> {
+    Some(match unit {
+        "m" => ("length", 1.0),
+        "cm" => ("length", 0.01),
+        "mm" => ("length", 0.001),
+        "ft" => ("length", 0.3048),
+        "in" => ("length", 0.0254),
+        "kg" => ("mass", 1.0),
+        "g" => ("mass", 0.001),
+        "lb" => ("mass", 0.453_592_37),
+        "oz" => ("mass", 0.028_349_523_125),
+        "l" => ("volume", 1.0),
+        "ml" => ("volume", 0.001),
+        _ => return None,
+    })
+}
+
+/// `quantity` in `from` units expressed in `to` units, or `None` if the two
+/// don't measure the same thing (feet and pounds, boxes and each).
+fn convert_quantity(quantity: f64, from: &str, to: &str) -> Option<f64> {
+    if from == to {
+        return Some(quantity);
+    }
+    let (from_dim, from_scale) = unit_scale(from)?;
+    let (to_dim, to_scale) = unit_scale(to)?;
+    (from_dim == to_dim).then(|| quantity * from_scale / to_scale)
+}
+
+/// Up to three decimals, without trailing zeros: 2, 2.5, 0.125.
+fn format_number(n: f64) -> String {
+    let rounded = (n * 1000.0).round() / 1000.0;
+    // Avoid printing "-0" for tiny negative float residue.
+    format!("{}", if rounded == 0.0 { 0.0 } else { rounded })
+}
+
+/// "3" for counted things, "2.5 m" otherwise.
+fn format_quantity(quantity: f64, unit: &str) -> String {
+    if unit == "each" {
+        format_number(quantity)
+    } else {
+        format!("{} {unit}", format_number(quantity))
+    }
+}
+
+/// "3 × hammer" or "2.5 m 14 AWG wire". `name` is used as given, so escape
+/// it first when building HTML.
+fn format_amount(quantity: f64, unit: &str, name: &str) -> String {
+    if unit == "each" {
+        format!("{} × {name}", format_number(quantity))
+    } else {
+        format!("{} {name}", format_quantity(quantity, unit))
+    }
+}
+
+fn render_unit_datalist() -> String {
+    let mut html = String::from(r#"<datalist id="units">"#);
+    for unit in COMMON_UNITS {
+        html.push_str(&format!(r#"<option value="{unit}">"#));
+    }
+    html.push_str("</datalist>");
+    html
+}
+
+fn load_locations(conn: &Connection) -> rusqlite::Result<Vec<Location>> {
+    let mut stmt = conn.prepare("SELECT id, name FROM locations ORDER BY name")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Location {
+            id: row.get(0)?,
+            name: row.get(1)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Id of the location called `name`, created if new. Names match case
+/// insensitively with runs of whitespace collapsed; blank means none.
+fn resolve_location(conn: &Connection, name: Option<&str>) -> rusqlite::Result<Option<i64>> {
+    let name = name
+        .unwrap_or("")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if name.is_empty() {
+        return Ok(None);
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO locations (name) VALUES (?1)",
+        params![name],
+    )?;
+    conn.query_row(
+        "SELECT id FROM locations WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+    )
+    .map(Some)
+}
+
+/// Where a container is, through its enclosing containers if needed.
+fn container_location(
+    conn: &Connection,
+    container_id: Option<i64>,
+) -> rusqlite::Result<Option<Location>> {
+    conn.query_row(
+        "SELECT l.id, l.name FROM container_locations cl JOIN locations l ON l.id = cl.location_id
+         WHERE cl.container_id = ?1",
+        params![container_id],
+        |row| {
+            Ok(Location {
+                id: row.get(0)?,
+                name: row.get(1)?,
+            })
+        },
+    )
+    .optional()
+}
+
+/// Suggestions for inputs with `list="locations"`.
+fn render_location_datalist(locations: &[Location]) -> String {
+    let mut html = String::from(r#"<datalist id="locations">"#);
+    for l in locations {
+        html.push_str(&format!(r#"<option value="{}">"#, html_escape(&l.name)));
+    }
+    html.push_str("</datalist>");
+    html
+}
+
+/// One container label as a ZPL `^XA`…`^XZ` block; several can go to the
+/// printer in one job. `page_url`, if given, is printed as a QR code in the
+/// top right corner, above the logo.
+fn zebra_label(items: &[Item], container_name: Option<&str>, page_url: Option<&str>) -> String {
+    // The label font is plain ASCII.
+    let header = container_name
+        .unwrap_or_default()
+        .replace(PATH_SEPARATOR, " > ");
     let mut zpl_body = String::new();
     let mut y = 80;
 
     for item in items {
         zpl_body.push_str(&format!(
-            "^FO40,{}^FB525,3,0,L,0^ADN^FD{} x {}^FS\n",
-            y, item.quantity, item.name
+            "^FO40,{}^FB525,3,0,L,0^ADN^FD{}{}^FS\n",
+            y,
+            format_amount(item.quantity, &item.unit, &item.name).replace('×', "x"),
+            item.expires_on
+                .as_deref()
+                .map(|d| format!(" (exp {d})"))
+                .unwrap_or_default()
         ));
 
         y += 22;
     }
 
-    let zpl = format!(
+    format!(
         "^XA\
         ^PW812\
         ^LH0,0\
         ^FO560,150^GFA,2875,2875,25,,:::::J03FFE0IF8K03I03NF89S04,J07gXF80FFC,J0LF87LF8007F71IF3QFCMF8,J0LF07E01E0ER08J07JFC07E003FC,J0E7FF1FJ03E0ER08J063IFC07EI018,J06I03FJ0EF03J0DM0C8I0C3FFCF87F00E1,J06007FF800F07818I0D9K01CB00183F8I07IFE3,J0703IF80780781CI07FI0807FE00307FEI078I03,J0307IFCI01FC0EI03E001801FE00607IF007FI07,J038E0F9CI031E07007FE041800FF80C0FE0300FFE00E,J03801E0EI0E1F0380IFC61F01BE01C1FEJ0F87C0C,J0180301F00383F80C03FFE3BC03F80383FFI01F80F1C,J01C3C07F80607FC0603F601F80E4C0607E38001FE0018,K0C001C78180E3C03027207F808440C0FE1C003FF0018,K0E00703C70183E01803207FE0080381FF06007C7C03,K0600C03C00603F00E01201838080703F38180F80F03,K070700EE00C0C78018I01J081C07F8E063F80386,K03800387030183E00F8003K0700FFC7003FE01C4,K03800707860303F003EM01E03FCE1807E3800C,K01C01C0FC8060C7C007M0F007F870C07E1F808,L0C0701CE018187F001F8J07C00FFC3860FF03E18,L0E04071FI0307BC003EI0FE003F8E0C00FFC0018,L06I0E3F80060C1F8001IFJ07F860601F9F003,L03001873C00C181BFO01F0C30303FC7806,L018030E3F0183031F8N0FF871800F9E1C06,L0180C1C7F83060211FCK01FF9830C01F87060C,M0C0030E7C20C0631FFEI01IF9C18603FC1800C,M04006183EJ0C613FFC3JFB8C0C2079E0E018,M06018307EI0184233KFE71860400F8E07038,M03I0608FI010C621JFEE30C30201FC70387,M01I0C10FC0021846363F18630410103DC3800E,N0801820BEI01084263B0821860800FCE1C00C,N04030411F800218426330C3083I01FC70E01C,N06020831FC00410844330C10C1I03FC306038,N038010617EI02084433041041I0F8E38307,N01C020C21F800610843184186I01F861C10E,K06I0E041861FF00400843186082I0FFC60C01C,K0CI070010C17FF08008C318208I03F9C306038,J01CI038021823IF0010C30820C001FF0E18307,J0388001C001061IF80308308004007E3861C30E,J0398I0E0060C33FFCI08M01E61870C11C0033,J0618I0700C0821IFE008M0FE60C3060180073,J0E3J0380018431IFO03FE30C10303800C6,J0C3J01E003082187FCM03FE6386180060018C,J086K0F0020863063JF801FFEE73861800C00318,K0CK0380010C20C3OFC631C6080380023,001818K01C003084187OF8631C30C06I02,007EN0700210C1071LF9FC631C3040C,00FF8M0380421830E00KF9FC218E1843800E,00C3E0CL0E0041861F007JF8FE2186I07001F84,00E0F0CL038083041FF01JFCFE3082001C0031CE,00E07FCL01E002083BFC0JFCE6308200380030EF,00C01F8M0380600707F01FFCC6210C2006I020FF,K02O0E0C00F81F80FC8E6318I01CI0207E,U070801FF07F07CCE731J07L03E,U01F803FFE0F8FCC6711I03C,V07C0783F87DFCC6311I0E,K0EP01F1F80FE3FFC4639101F8,00180FQ07FF801F1FEC661900F8,00381FQ01IF0070FE446I0FC,00381BS0FFC039FCK0FCP078,00183T0E1F01FFE8041FEN0181F8,00383S01C0FC0MFEM060301FC,00383S03803F0FF7IFCN0F070398,00383S0EI078FES0F8E038,00383R038I03CFCS0DCC03,00383R0EJ01FFCT0F806,003831P038K0FF8T07806,003833P0EL0FFU07806,003833O03CK01FEU0F806,00303FO078K03FCU0FC06,00303EO0EJ0303F8T01CE06,002018N038J07077U038F07,T07K070EV03870318,T0E01I071EV070783B8,S01C01800E1CV0E0381F8,S03I0C01E3CU01E03C0E,S06I0C07C78U01C01C,S0CI0C3F8F8U01C,R018I0C7F1F,R018300C7C1E,R030701CF03C,R060E009F078,R061E389E0F,R0C3C799E1F,Q01C7C799E1E,Q018F8F31E38,Q031F9D23FF,Q021B1927FE,Q06323B3FFC,Q06723B1FFC,Q0C667103F8,Q09C4E187F,P0198CE1FF7,P01F09C0EE6,P01E19C006E,P0181B800FE,R01B800FC,R01FI0FC,R01EI0F8,R03CI0F8,R01J0F,V01F,:W0E,W04,,::^FS\
         ^FO40,40^FB525,3,0,L,0^AEN,20,20^FD{header}^FS\
+        {qr}\
         {body}\
         ^XZ",
+        // Model 2, magnification 4, medium error correction, automatic
+        // data mode: about an inch square for a LAN URL.
+        qr = page_url
+            .map(|url| format!("^FO620,0^BQN,2,4^FDMA,{url}^FS"))
+            .unwrap_or_default(),
         body = zpl_body
-    );
+    )
+}
+
+/// Hands raw ZPL to CUPS for `printer_name`.
+fn send_zpl(printer_name: &str, zpl: &str) -> Result<(), Box<dyn std::error::Error>> {
     println!("Generated ZPL:\n{}", zpl);
-    const PRINTER_NAME: &str = "zebra";
 
-    let mut child = std::process::Command::new("lp")
+    let mut child = Command::new("lp")
         .arg("-d")
-        .arg(PRINTER_NAME)
+        .arg(printer_name)
         .arg("-o")
         .arg("raw")
         .arg("-")
-        .stdin(std::process::Stdio::piped())
+        .stdin(Stdio::piped())
         .spawn()?;
 
     if let Some(stdin) = &mut child.stdin {
@@ -595,20 +6348,93 @@ fn print_zebra_label(
 
     let status = child.wait()?;
     if !status.success() {
-        eprintln!("CUPS exited with status {status}");
+        return Err(format!("CUPS exited with status {status}").into());
     }
 
     Ok(())
 }
 
+/// Characters of font D (10 dots wide) that fit the 740-dot field block.
+const SHOPPING_LINE_CHARS: usize = 74;
+
+/// Lines `text` takes when word-wrapped at `width` characters, the way a
+/// ZPL `^FB` block breaks it.
+fn wrapped_line_count(text: &str, width: usize) -> usize {
+    let mut lines = 1;
+    let mut used = 0;
+    for word in text.split_whitespace() {
+        let len = word.chars().count();
+        let needed = if used == 0 { len } else { used + 1 + len };
+        if used > 0 && needed > width {
+            lines += 1;
+            used = len;
+        } else {
+            used = needed;
+        }
+        // A word longer than the line is broken across lines.
+        while used > width {
+            lines += 1;
+            used -= width;
+        }
+    }
+    lines
+}
+
+/// Receipt-style label for the shopping list: a header, then one line per
+/// item under each location, with the label length grown to fit.
+fn shopping_list_zpl(groups: &[(String, Vec<ShoppingItem>)]) -> String {
+    let mut zpl_body = String::new();
+    let mut y = 90;
+
+    for (location, items) in groups {
+        zpl_body.push_str(&format!("^FO30,{y}^A0N,28,28^FD{location}^FS\n"));
+        y += 36;
+        for s in items {
+            let unit = &s.item.unit;
+            let line = format!(
+                "[ ] {} (have {}, keep {})",
+                s.item.name,
+                format_quantity(s.item.quantity, unit),
+                format_quantity(s.item.min_quantity.unwrap_or_default(), unit)
+            );
+            // Give the field block as many lines as the text wraps to, so a
+            // long line pushes the next one down instead of printing over it.
+            let lines = wrapped_line_count(&line, SHOPPING_LINE_CHARS);
+            zpl_body.push_str(&format!("^FO40,{y}^FB740,{lines},0,L,0^ADN^FD{line}^FS\n"));
+            y += 26 * lines;
+        }
+        y += 14;
+    }
+
+    format!(
+        "^XA\
+        ^PW812\
+        ^LL{length}\
+        ^LH0,0\
+        ^FO30,30^A0N,40,40^FDShopping list^FS\
+        {body}\
+        ^XZ",
+        length = y + 30,
+        body = zpl_body
+    )
+}
+
+/// The container a form picked: a typed new name wins, then a new
+/// auto-numbered one when `auto_scheme` is given, then the dropdown choice.
 fn choose_container(
     tx: &Transaction,
     container_select: Option<i64>,
     container_new: Option<String>,
-) -> rusqlite::Result<(Option<i64>, Option<String>)> {
-    if let Some(new_name) = normalize_optional(container_new) {
+    auto_scheme: Option<&str>,
+) -> Result<(Option<i64>, Option<String>), AppError> {
+    let container_new = match (normalize_optional(container_new), auto_scheme) {
+        (Some(name), _) => Some(name),
+        (None, Some(scheme)) => Some(next_container_name(tx, scheme)?),
+        (None, None) => None,
+    };
+    if let Some(new_name) = container_new {
         //insert new container if not exists
-        tx.execute(
+        let inserted = tx.execute(
             "INSERT OR IGNORE INTO containers (name) VALUES (?1)",
             params![&new_name],
         )?;
@@ -619,16 +6445,32 @@ fn choose_container(
             |row| row.get(0),
         )?;
 
+        if inserted == 1 {
+            let after = container_snapshot(tx, id)?;
+            record_event(
+                tx,
+                EntityKind::Container,
+                id,
+                EventAction::Create,
+                None,
+                after,
+                EventSource::WebForm,
+            )?;
+        }
+
         return Ok((Some(id), Some(new_name)));
     }
 
     if let Some(id) = container_select {
         //lookup container name
-        let name: String = tx.query_row(
-            "SELECT name FROM containers WHERE id = ?1",
-            params![id],
-            |row| row.get(0),
-        )?;
+        let name: String = tx
+            .query_row(
+                "SELECT name FROM containers WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| AppError::NotFound(format!("Container #{id}")))?;
 
         return Ok((Some(id), Some(name)));
     }
@@ -636,6 +6478,96 @@ fn choose_container(
     Ok((None, None))
 }
 
+/// All values submitted under `key`, for forms that repeat a field (checkboxes).
+fn form_values<'a>(fields: &'a [(String, String)], key: &'a str) -> impl Iterator<Item = &'a str> {
+    fields
+        .iter()
+        .filter(move |(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+/// Dropdown value asking for a new auto-numbered container.
+const NEW_CONTAINER_OPTION: &str = "new";
+
+/// The naming scheme to number a new container with, if the dropdown asked
+/// for one and a scheme is configured.
+fn auto_name_scheme(config: &Config, container_select: Option<&str>) -> Option<String> {
+    config
+        .name_scheme()
+        .filter(|_| container_select == Some(NEW_CONTAINER_OPTION))
+        .map(str::to_string)
+}
+
+/// `scheme` with its `{}` or `{:0N}` placeholder replaced by `n`; `None` if
+/// it has no usable placeholder.
+fn format_scheme(scheme: &str, n: i64) -> Option<String> {
+    let start = scheme.find('{')?;
+    let end = start + scheme[start..].find('}')?;
+    let number = match &scheme[start + 1..end] {
+        "" => n.to_string(),
+        spec => {
+            let width: usize = spec.strip_prefix(":0")?.parse().ok()?;
+            format!("{n:0width$}")
+        }
+    };
+    Some(format!(
+        "{}{number}{}",
+        &scheme[..start],
+        &scheme[end + 1..]
+    ))
+}
+
+/// The name `next_container_name` would hand out now, without taking it.
+fn peek_container_name(conn: &Connection, scheme: &str) -> rusqlite::Result<String> {
+    let last: i64 = conn
+        .query_row(
+            "SELECT last FROM name_sequences WHERE scheme = ?1",
+            params![scheme],
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or(0);
+    let mut n = last + 1;
+    loop {
+        let name = format_scheme(scheme, n).unwrap_or_default();
+        if !container_name_taken(conn, &name)? {
+            return Ok(name);
+        }
+        n += 1;
+    }
+}
+
+/// Takes the next number in `scheme`, skipping any already used by a
+/// container named by hand.
+fn next_container_name(conn: &Connection, scheme: &str) -> rusqlite::Result<String> {
+    loop {
+        let n: i64 = conn.query_row(
+            "INSERT INTO name_sequences (scheme, last) VALUES (?1, 1)
+             ON CONFLICT (scheme) DO UPDATE SET last = last + 1
+             RETURNING last",
+            params![scheme],
+            |row| row.get(0),
+        )?;
+        let name = format_scheme(scheme, n).unwrap_or_default();
+        if !container_name_taken(conn, &name)? {
+            return Ok(name);
+        }
+    }
+}
+
+fn container_name_taken(conn: &Connection, name: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM containers WHERE name = ?1)",
+        params![name],
+        |row| row.get(0),
+    )
+}
+
+/// Converts the dropdown value to an id; "" and junk both mean no container.
+fn parse_container_select(value: Option<&str>) -> Option<i64> {
+    value.and_then(|s| s.trim().parse::<i64>().ok())
+}
+
 fn normalize_optional(opt: Option<String>) -> Option<String> {
     opt.and_then(|s| {
         let trimmed = s.trim();
@@ -646,3 +6578,296 @@ fn normalize_optional(opt: Option<String>) -> Option<String> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh in-memory database with every migration applied.
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", "ON").unwrap();
+        run_migrations(&mut conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn parse_date_accepts_real_calendar_days() {
+        assert_eq!(parse_date("2027-03-31").as_deref(), Some("2027-03-31"));
+        assert_eq!(parse_date(" 2024-02-29 ").as_deref(), Some("2024-02-29"));
+        assert_eq!(parse_date("2000-02-29").as_deref(), Some("2000-02-29"));
+    }
+
+    #[test]
+    fn parse_date_rejects_malformed_and_impossible_dates() {
+        for raw in [
+            "",
+            "2027-3-31",
+            "27-03-31",
+            "2027/03/31",
+            "2026-+1-01",
+            "+027-03-31",
+            "2027-03-+1",
+            "2027-04-31",
+            "2023-02-29",
+            "1900-02-29",
+            "2027-13-01",
+            "2027-00-10",
+            "2027-01-00",
+            "March 2027",
+        ] {
+            assert_eq!(parse_date(raw), None, "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn canonical_unit_folds_spellings() {
+        assert_eq!(canonical_unit(""), "each");
+        assert_eq!(canonical_unit(" Pcs. "), "each");
+        assert_eq!(canonical_unit("Metres"), "m");
+        assert_eq!(canonical_unit("lbs"), "lb");
+        assert_eq!(canonical_unit("Boxes"), "box");
+        // Unknown units are kept, lowercased.
+        assert_eq!(canonical_unit("Spools"), "spools");
+    }
+
+    #[test]
+    fn convert_quantity_within_a_dimension_only() {
+        let close = |a: Option<f64>, b: f64| a.is_some_and(|a| (a - b).abs() < 1e-9);
+        assert!(close(convert_quantity(250.0, "cm", "m"), 2.5));
+        assert!(close(convert_quantity(1.0, "ft", "in"), 12.0));
+        assert!(close(convert_quantity(16.0, "oz", "lb"), 1.0));
+        assert!(close(convert_quantity(1.5, "l", "ml"), 1500.0));
+        assert!(close(convert_quantity(3.0, "box", "box"), 3.0));
+        assert_eq!(convert_quantity(1.0, "ft", "lb"), None);
+        assert_eq!(convert_quantity(1.0, "box", "each"), None);
+        assert_eq!(convert_quantity(1.0, "spools", "m"), None);
+    }
+
+    #[test]
+    fn parsed_quantities_must_be_positive_numbers() {
+        let parsed = |name: &str, quantity: f64| ParsedItem {
+            name: name.to_string(),
+            quantity,
+            unit: None,
+            min_quantity: None,
+            expires_on: None,
+        };
+        assert!(check_parsed_quantities(&[parsed("glue", 2.0), parsed("wire", 0.5)]).is_ok());
+        for quantity in [0.0, -2.0, f64::NAN, f64::INFINITY] {
+            let err = check_parsed_quantities(&[parsed("glue", 2.0), parsed("tape", quantity)])
+                .unwrap_err();
+            assert!(
+                matches!(&err, AppError::BadRequest(m) if m.contains("tape") && !m.contains("glue"))
+            );
+        }
+    }
+
+    #[test]
+    fn format_scheme_fills_the_placeholder() {
+        assert_eq!(format_scheme("BIN-{:04}", 7).as_deref(), Some("BIN-0007"));
+        assert_eq!(
+            format_scheme("BIN-{:04}", 12345).as_deref(),
+            Some("BIN-12345")
+        );
+        assert_eq!(format_scheme("Box {}", 3).as_deref(), Some("Box 3"));
+        assert_eq!(format_scheme("{:02}-shelf", 5).as_deref(), Some("05-shelf"));
+    }
+
+    #[test]
+    fn format_scheme_rejects_schemes_without_a_usable_placeholder() {
+        for scheme in ["BIN", "BIN-{", "BIN-{:4}", "BIN-{:0}", "BIN-{x}", "BIN-}{"] {
+            assert_eq!(format_scheme(scheme, 1), None, "{scheme:?}");
+        }
+    }
+
+    #[test]
+    fn cell_labels_round_trip() {
+        assert_eq!(cell_label(0, 0), "A1");
+        assert_eq!(cell_label(3, 5), "D6");
+        assert_eq!(cell_label(25, 49), "Z50");
+        for (row, col) in [(0, 0), (3, 5), (25, 49)] {
+            assert_eq!(parse_cell(&cell_label(row, col)), Some((row, col)));
+        }
+    }
+
+    #[test]
+    fn parse_cell_rejects_other_text() {
+        for label in ["", "A", "a1", "1A", "AA1", "B-2", "B+2", "B0", "Cx"] {
+            assert_eq!(parse_cell(label), None, "{label:?}");
+        }
+    }
+
+    #[test]
+    fn migration_15_groups_location_hints() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", "ON").unwrap();
+        for sql in &MIGRATIONS[..14] {
+            conn.execute_batch(sql).unwrap();
+        }
+        conn.pragma_update(None, "user_version", 14).unwrap();
+        conn.execute_batch(
+            "INSERT INTO containers (id, name) VALUES (1, 'Toolbox'), (2, 'Shelf'), (3, 'Empty');
+             INSERT INTO items (id, name, quantity, container_id, location_hint) VALUES
+                 (1, 'drill', 1, 1, 'Basement'),
+                 (2, 'saw', 1, 1, ' basement '),
+                 (3, 'glue', 1, 2, 'Garage'),
+                 (4, 'tape', 1, 2, NULL),
+                 (5, 'nails', 1, NULL, 'garage'),
+                 (6, 'pen', 1, NULL, '  '),
+                 (7, 'rake', 1, NULL, ' Back  yard'),
+                 (8, 'hose', 1, NULL, 'back' || char(9) || 'YARD ');",
+        )
+        .unwrap();
+
+        run_migrations(&mut conn).unwrap();
+
+        // Case and spacing variants fold into the first spelling; blanks make
+        // no location.
+        let names: Vec<String> = load_locations(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|l| l.name)
+            .collect();
+        assert_eq!(names, ["Back yard", "Basement", "Garage"]);
+        let location_of = |sql: &str, id: i64| -> Option<String> {
+            conn.query_row(sql, params![id], |row| row.get(0)).unwrap()
+        };
+        let own_item = "SELECT l.name FROM items i LEFT JOIN locations l ON l.id = i.location_id
+                        WHERE i.id = ?1";
+        let container = "SELECT l.name FROM containers c
+                         LEFT JOIN locations l ON l.id = c.location_id WHERE c.id = ?1";
+        let effective = "SELECT (SELECT name FROM item_locations WHERE item_id = ?1)";
+
+        // Every item in the toolbox agreed, so the toolbox takes the location
+        // and its items inherit it instead of keeping their own.
+        assert_eq!(location_of(container, 1).as_deref(), Some("Basement"));
+        assert_eq!(location_of(own_item, 1), None);
+        assert_eq!(location_of(own_item, 2), None);
+        assert_eq!(location_of(effective, 2).as_deref(), Some("Basement"));
+
+        // One shelf item had no hint, so the shelf stays unplaced.
+        assert_eq!(location_of(container, 2), None);
+        assert_eq!(location_of(own_item, 3).as_deref(), Some("Garage"));
+        assert_eq!(location_of(effective, 4), None);
+        assert_eq!(location_of(container, 3), None);
+
+        assert_eq!(location_of(own_item, 5).as_deref(), Some("Garage"));
+        assert_eq!(location_of(own_item, 6), None);
+        assert_eq!(location_of(own_item, 8).as_deref(), Some("Back yard"));
+        // A later entry finds the migrated location rather than adding one.
+        let back_yard: Option<i64> = conn
+            .query_row("SELECT location_id FROM items WHERE id = 7", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(
+            resolve_location(&conn, Some("back yard")).unwrap(),
+            back_yard
+        );
+        assert!(load_item(&conn, 1).is_ok());
+    }
+
+    #[test]
+    fn shopping_lines_wrap_like_the_field_block() {
+        assert_eq!(wrapped_line_count("[ ] glue (have 1, keep 2)", 74), 1);
+        assert_eq!(wrapped_line_count(&"word ".repeat(20), 74), 2);
+        assert_eq!(wrapped_line_count(&"x".repeat(150), 74), 3);
+        assert_eq!(wrapped_line_count("", 74), 1);
+    }
+
+    #[test]
+    fn merging_matches_a_location_inherited_from_the_container() {
+        let mut conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO locations (id, name) VALUES (1, 'Garage');
+             INSERT INTO containers (id, name, location_id) VALUES (1, 'Bin 3', 1);
+             INSERT INTO items (id, name, quantity, container_id) VALUES (1, 'screws', 10, 1);",
+        )
+        .unwrap();
+
+        let screws = Item {
+            id: 0,
+            name: "Screws".to_string(),
+            quantity: 5.0,
+            unit: "each".to_string(),
+            min_quantity: None,
+            expires_on: None,
+            container_id: Some(1),
+            location: Some("Garage".to_string()),
+            location_id: Some(1),
+            lent: 0.0,
+        };
+        let tx = conn.transaction().unwrap();
+        let outcomes = save_items_tx(&tx, &[screws], true, EventSource::WebForm).unwrap();
+        tx.commit().unwrap();
+
+        assert!(
+            matches!(outcomes[..], [SaveOutcome::Merged { id: 1, quantity, .. }] if quantity == 15.0)
+        );
+    }
+
+    #[test]
+    fn valuation_groups_same_named_drawers_by_full_path() {
+        let conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO containers (id, name) VALUES (1, 'Red cabinet'), (2, 'Blue cabinet');
+             INSERT INTO containers (id, name, kind, parent_id, cell)
+             VALUES (3, 'Red cabinet A1', 'drawer', 1, 'A1'), (4, 'Blue cabinet A1', 'drawer', 2, 'A1');
+             INSERT INTO items (name, quantity, container_id) VALUES ('fuses', 3, 3), ('relays', 2, 4);",
+        )
+        .unwrap();
+
+        let report = load_valuation(&conn).unwrap();
+        let labels: Vec<&str> = report.iter().map(ValuationRow::container).collect();
+        assert_eq!(
+            labels,
+            [
+                format!("Blue cabinet{PATH_SEPARATOR}A1"),
+                format!("Red cabinet{PATH_SEPARATOR}A1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lent_stock_counts_as_owned_until_returned() {
+        let mut conn = test_db();
+        conn.execute(
+            "INSERT INTO items (name, quantity, unit, min_quantity, purchase_price)
+             VALUES ('brass rod', 2.5, 'lb', 2, 1200.50)",
+            [],
+        )
+        .unwrap();
+        let id = conn.last_insert_rowid();
+
+        let tx = conn.transaction().unwrap();
+        lend_item(&tx, id, "Sam", 1.0, None).unwrap();
+        tx.commit().unwrap();
+
+        let item = load_item(&conn, id).unwrap();
+        assert!((item.quantity - 1.5).abs() < QUANTITY_EPSILON);
+        assert!((item.lent - 1.0).abs() < QUANTITY_EPSILON);
+        assert!(!item.is_low());
+        let value = load_valuation(&conn).unwrap()[0].value().unwrap();
+        assert!((value - 3001.25).abs() < 0.005);
+        assert!(load_shopping_list(&conn).unwrap().is_empty());
+
+        let loan_id: i64 = conn
+            .query_row(
+                "SELECT id FROM loans WHERE item_id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let tx = conn.transaction().unwrap();
+        return_loan(&tx, loan_id).unwrap();
+        tx.commit().unwrap();
+
+        let item = load_item(&conn, id).unwrap();
+        assert!((item.quantity - 2.5).abs() < QUANTITY_EPSILON);
+        assert_eq!(item.lent, 0.0);
+        let value = load_valuation(&conn).unwrap()[0].value().unwrap();
+        assert!((value - 3001.25).abs() < 0.005);
+        assert!(load_shopping_list(&conn).unwrap().is_empty());
+    }
+}